@@ -6,6 +6,7 @@ use std::path::Path;
 fn main() -> std::io::Result<()> {
 
     println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-changed=resources");
 
     let mut resources = env::current_dir()
         .expect("Source directory not found")
@@ -0,0 +1,20 @@
+pub mod terminal;
+pub mod screen;
+
+pub use terminal::CrosstermBackend;
+pub use screen::{Cell, VirtualScreen};
+
+use std::io::{self, Write};
+
+use crate::TermUnit;
+
+// Where the game is drawn. Crossterm commands are written to it, and it
+// sets the terminal up and tells its size
+pub trait Backend : Write {
+    fn size(&self) -> io::Result<(TermUnit, TermUnit)>;
+
+    // Raw mode and alternate screen, undone by leave
+    fn enter(&mut self) -> io::Result<()>;
+
+    fn leave(&mut self) -> io::Result<()>;
+}
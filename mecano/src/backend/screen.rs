@@ -0,0 +1,413 @@
+use std::{
+    cell::{Ref, RefCell},
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crossterm::style::{Attribute, Attributes, Color};
+
+use crate::{backend::Backend, TermUnit};
+
+const ESC : u8 = 0x1b;
+// Numbers of a sequence kept, 2;r;g;b colors need the most
+const MAX_PARAMS : usize = 8;
+// Bytes written before they are drawn, when nothing reads the screen
+const MAX_PENDING : usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub c : char,
+    pub fg : Color,
+    pub bg : Color,
+    pub attributes : Attributes,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        return Cell {
+            c : ' ',
+            fg : Color::Reset,
+            bg : Color::Reset,
+            attributes : Attributes::default(),
+        };
+    }
+}
+
+struct Grid {
+    size : (TermUnit, TermUnit),
+    cells : Vec<Vec<Cell>>,
+    cursor : (TermUnit, TermUnit),
+    // Style of the next chars printed
+    pen : Cell,
+    cursor_shown : bool,
+    entered : bool,
+    // Bytes written but not drawn yet, they are drawn when the screen is
+    // read or flushed
+    pending : Vec<u8>,
+}
+
+// In-memory terminal that understands the escape sequences crossterm
+// writes, so tests can check what was drawn. Clones share the screen
+#[derive(Clone)]
+pub struct VirtualScreen {
+    grid : Rc<RefCell<Grid>>,
+}
+
+impl VirtualScreen {
+    pub fn new(size : (TermUnit, TermUnit)) -> VirtualScreen {
+        let grid = Grid {
+            size,
+            cells : vec![vec![Cell::default(); size.0 as usize]; size.1 as usize],
+            cursor : (0, 0),
+            pen : Cell::default(),
+            cursor_shown : true,
+            entered : false,
+            pending : Vec::new(),
+        };
+        return VirtualScreen { grid : Rc::new(RefCell::new(grid)) };
+    }
+
+    // Content is kept where it fits
+    pub fn resize(&self, size : (TermUnit, TermUnit)) {
+        let mut grid = self.grid.borrow_mut();
+        grid.parse();
+        grid.cells.resize(size.1 as usize, vec![Cell::default(); size.0 as usize]);
+        for row in grid.cells.iter_mut() {
+            row.resize(size.0 as usize, Cell::default());
+        }
+        grid.size = size;
+        grid.cursor.0 = grid.cursor.0.min(size.0.saturating_sub(1));
+        grid.cursor.1 = grid.cursor.1.min(size.1.saturating_sub(1));
+    }
+
+    // Drawn grid, up to date with everything written
+    fn grid(&self) -> Ref<'_, Grid> {
+        self.grid.borrow_mut().parse();
+        return self.grid.borrow();
+    }
+
+    pub fn cell(&self, x : TermUnit, y : TermUnit) -> Cell {
+        return self.grid().cells[y as usize][x as usize];
+    }
+
+    pub fn line(&self, y : TermUnit) -> String {
+        return self.grid().cells[y as usize].iter().map(|cell| cell.c).collect();
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let n_lines = self.grid.borrow().size.1;
+        return (0..n_lines).map(|y| self.line(y)).collect();
+    }
+
+    // Position of the first char of a text on the screen
+    pub fn find(&self, text : &str) -> Option<(TermUnit, TermUnit)> {
+        for (y, line) in self.lines().iter().enumerate() {
+            if let Some(byte_idx) = line.find(text) {
+                let x = line[..byte_idx].chars().count();
+                return Some((x as TermUnit, y as TermUnit));
+            }
+        }
+        return None;
+    }
+
+    pub fn get_cursor(&self) -> (TermUnit, TermUnit) {
+        return self.grid().cursor;
+    }
+
+    pub fn is_cursor_shown(&self) -> bool {
+        return self.grid().cursor_shown;
+    }
+
+    pub fn is_entered(&self) -> bool {
+        return self.grid.borrow().entered;
+    }
+}
+
+impl Write for VirtualScreen {
+    fn write(&mut self, buf : &[u8]) -> io::Result<usize> {
+        let mut grid = self.grid.borrow_mut();
+        grid.pending.extend_from_slice(buf);
+        if grid.pending.len() > MAX_PENDING {
+            grid.parse();
+        }
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.grid.borrow_mut().parse();
+        return Ok(());
+    }
+}
+
+impl Backend for VirtualScreen {
+    fn size(&self) -> io::Result<(TermUnit, TermUnit)> {
+        return Ok(self.grid.borrow().size);
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        self.grid.borrow_mut().entered = true;
+        return Ok(());
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        let mut grid = self.grid.borrow_mut();
+        grid.entered = false;
+        grid.cursor_shown = true;
+        return Ok(());
+    }
+}
+
+impl Grid {
+    // Applies every complete sequence and char, the rest waits for more bytes
+    fn parse(&mut self) {
+        let mut pending = std::mem::take(&mut self.pending);
+        let mut idx = 0;
+        while idx < pending.len() {
+            let consumed = match pending[idx] {
+                ESC => self.escape(&pending[idx..]),
+                b'\r' => { self.cursor.0 = 0; Some(1) }
+                b'\n' => { self.move_by(0, 1); Some(1) }
+                _ => self.print(&pending[idx..]),
+            };
+            match consumed {
+                Some(n_bytes) => idx += n_bytes,
+                None => break,
+            }
+        }
+        pending.drain(..idx);
+        self.pending = pending;
+    }
+
+    fn print(&mut self, bytes : &[u8]) -> Option<usize> {
+        let n_bytes = match bytes[0] {
+            0x20..=0x7e => {
+                self.put(bytes[0] as char);
+                return Some(1);
+            }
+            0xf0..=0xff => 4,
+            0xe0..=0xef => 3,
+            0xc0..=0xdf => 2,
+            _ => 1,
+        };
+        if bytes.len() < n_bytes {
+            return None;
+        }
+        let c = std::str::from_utf8(&bytes[..n_bytes])
+            .map_or(char::REPLACEMENT_CHARACTER, |s| s.chars().next().unwrap_or(' '));
+        if !c.is_control() {
+            self.put(c);
+        }
+        return Some(n_bytes);
+    }
+
+    fn put(&mut self, c : char) {
+        // Like terminals, the cursor wraps when printing past the last column
+        if self.cursor.0 >= self.size.0 {
+            self.cursor.0 = 0;
+            self.move_by(0, 1);
+        }
+        let (x, y) = self.cursor;
+        if let Some(cell) = self.cells.get_mut(y as usize).and_then(|row| row.get_mut(x as usize)) {
+            *cell = Cell { c, ..self.pen };
+        }
+        self.cursor.0 += 1;
+    }
+
+    fn escape(&mut self, bytes : &[u8]) -> Option<usize> {
+        if bytes.len() < 2 {
+            return None;
+        }
+        if bytes[1] != b'[' {
+            return Some(2);
+        }
+
+        // Numbers are read as the bytes go, up to the command
+        let private = bytes.get(2) == Some(&b'?');
+        let mut values : [u16; MAX_PARAMS] = [0; MAX_PARAMS];
+        let mut n_values = 1;
+        let mut idx = if private { 3 } else { 2 };
+        loop {
+            let byte = *bytes.get(idx)?;
+            match byte {
+                b'0'..=b'9' => {
+                    let value = &mut values[n_values - 1];
+                    *value = value.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                }
+                b';' if n_values < MAX_PARAMS => n_values += 1,
+                0x40..=0x7e => {
+                    self.control(private, &values[..n_values], byte as char);
+                    return Some(idx + 1);
+                }
+                _ => (),
+            }
+            idx += 1;
+        }
+    }
+
+    fn control(&mut self, private : bool, values : &[u16], command : char) {
+        let first = values[0];
+        if private {
+            if first == 25 {
+                self.cursor_shown = command == 'h';
+            }
+            return;
+        }
+
+        // Terminals move by one when asked to move by zero
+        let n = first.max(1) as i32;
+        match command {
+            'H' | 'f' => {
+                let y = first.max(1) - 1;
+                let x = values.get(1).copied().unwrap_or(1).max(1) - 1;
+                self.cursor = (x, y);
+                self.move_by(0, 0);
+            }
+            'G' => { self.cursor.0 = first.max(1) - 1; self.move_by(0, 0); }
+            'd' => { self.cursor.1 = first.max(1) - 1; self.move_by(0, 0); }
+            'A' => self.move_by(0, -n),
+            'B' => self.move_by(0, n),
+            'C' => self.move_by(n, 0),
+            'D' => self.move_by(-n, 0),
+            'E' => { self.cursor.0 = 0; self.move_by(0, n); }
+            'F' => { self.cursor.0 = 0; self.move_by(0, -n); }
+            'J' => self.clear_screen(first),
+            'K' => self.clear_line(first),
+            'm' => self.style(values),
+            _ => (),
+        }
+    }
+
+    fn move_by(&mut self, dx : i32, dy : i32) {
+        let max_x = self.size.0.saturating_sub(1) as i32;
+        let max_y = self.size.1.saturating_sub(1) as i32;
+        self.cursor.0 = (self.cursor.0 as i32 + dx).clamp(0, max_x) as TermUnit;
+        self.cursor.1 = (self.cursor.1 as i32 + dy).clamp(0, max_y) as TermUnit;
+    }
+
+    fn clear_screen(&mut self, mode : u16) {
+        let (x, y) = (self.cursor.0 as usize, self.cursor.1 as usize);
+        for (row_y, row) in self.cells.iter_mut().enumerate() {
+            for (cell_x, cell) in row.iter_mut().enumerate() {
+                let cleared = match mode {
+                    0 => (row_y, cell_x) >= (y, x),
+                    1 => (row_y, cell_x) <= (y, x),
+                    _ => true,
+                };
+                if cleared { *cell = Cell::default(); }
+            }
+        }
+    }
+
+    fn clear_line(&mut self, mode : u16) {
+        let x = self.cursor.0 as usize;
+        if let Some(row) = self.cells.get_mut(self.cursor.1 as usize) {
+            for (cell_x, cell) in row.iter_mut().enumerate() {
+                let cleared = match mode {
+                    0 => cell_x >= x,
+                    1 => cell_x <= x,
+                    _ => true,
+                };
+                if cleared { *cell = Cell::default(); }
+            }
+        }
+    }
+
+    // Select graphic rendition, colors and attributes
+    fn style(&mut self, values : &[u16]) {
+        let mut values = values.iter().copied();
+        while let Some(value) = values.next() {
+            match value {
+                0 => self.pen = Cell::default(),
+                38 => self.pen.fg = color(&mut values).unwrap_or(self.pen.fg),
+                39 => self.pen.fg = Color::Reset,
+                48 => self.pen.bg = color(&mut values).unwrap_or(self.pen.bg),
+                49 => self.pen.bg = Color::Reset,
+                22 => {
+                    self.pen.attributes.unset(Attribute::Bold);
+                    self.pen.attributes.unset(Attribute::Dim);
+                }
+                23 => self.pen.attributes.unset(Attribute::Italic),
+                24 => self.pen.attributes.unset(Attribute::Underlined),
+                27 => self.pen.attributes.unset(Attribute::Reverse),
+                1 => self.pen.attributes.set(Attribute::Bold),
+                4 => self.pen.attributes.set(Attribute::Underlined),
+                7 => self.pen.attributes.set(Attribute::Reverse),
+                _ => {
+                    let sgr = value.to_string();
+                    if let Some(attribute) = Attribute::iterator().find(|a| a.sgr() == sgr) {
+                        self.pen.attributes.set(attribute);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 8 bit colors come as 5;n and 24 bit ones as 2;r;g;b
+fn color(values : &mut impl Iterator<Item = u16>) -> Option<Color> {
+    return match values.next()? {
+        5 => Color::parse_ansi(&format!("5;{}", values.next()?)),
+        2 => {
+            let r = values.next()? as u8;
+            let g = values.next()? as u8;
+            let b = values.next()? as u8;
+            Some(Color::Rgb { r, g, b })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use crossterm::{
+        cursor::{self, MoveDown, MoveTo, MoveToColumn},
+        style::{Attribute, Color, Print, SetAttribute, SetForegroundColor},
+        terminal::{Clear, ClearType},
+        queue,
+    };
+
+    use super::VirtualScreen;
+
+    #[test]
+    fn draws_crossterm_commands() {
+        let mut screen = VirtualScreen::new((10, 3));
+        let red = Color::Rgb { r : 255, g : 0, b : 0 };
+        let _ = queue!(screen,
+            MoveTo(2, 1),
+            SetForegroundColor(red),
+            SetAttribute(Attribute::Underlined),
+            Print("añ"),
+            SetAttribute(Attribute::Reset),
+            Print("b"),
+            MoveDown(1),
+            MoveToColumn(0),
+            SetForegroundColor(Color::Green),
+            Print("┗━┛"),
+            cursor::Hide);
+
+        assert_eq!(screen.line(1), "  añb     ");
+        assert_eq!(screen.cell(2, 1).fg, red);
+        assert!(screen.cell(3, 1).attributes.has(Attribute::Underlined));
+        assert_eq!(screen.cell(4, 1).fg, Color::Reset);
+        assert!(!screen.cell(4, 1).attributes.has(Attribute::Underlined));
+        assert_eq!(screen.find("┗━┛"), Some((0, 2)));
+        assert_eq!(screen.cell(1, 2).fg, Color::Green);
+        assert_eq!(screen.get_cursor(), (3, 2));
+        assert!(!screen.is_cursor_shown());
+
+        // Sequences split between writes
+        let _ = screen.write(b"\x1b[1;1");
+        let _ = screen.write(&"Hé".as_bytes()[..2]);
+        let _ = screen.write(&"é".as_bytes()[1..]);
+        assert_eq!(screen.line(0), "é         ");
+
+        let _ = queue!(screen, MoveTo(8, 0), Print("xyz"));
+        assert_eq!(screen.line(0), "é       xy");
+        assert_eq!(screen.line(1), "z añb     ");
+
+        let _ = queue!(screen, Clear(ClearType::All));
+        assert!(screen.lines().iter().all(|line| line.trim().is_empty()));
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::{self, stdout, Stdout, Write};
+
+use crossterm::{
+    cursor,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    execute,
+};
+
+use crate::{backend::Backend, TermUnit};
+
+// The real terminal, through stdout
+pub struct CrosstermBackend {
+    out : Stdout,
+}
+
+impl CrosstermBackend {
+    pub fn new() -> CrosstermBackend {
+        return CrosstermBackend { out : stdout() };
+    }
+}
+
+impl Default for CrosstermBackend {
+    fn default() -> Self {
+        return CrosstermBackend::new();
+    }
+}
+
+impl Write for CrosstermBackend {
+    fn write(&mut self, buf : &[u8]) -> io::Result<usize> {
+        return self.out.write(buf);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.out.flush();
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn size(&self) -> io::Result<(TermUnit, TermUnit)> {
+        return terminal::size();
+    }
+
+    fn enter(&mut self) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        return execute!(self.out, EnterAlternateScreen);
+    }
+
+    fn leave(&mut self) -> io::Result<()> {
+        terminal::disable_raw_mode()?;
+        return execute!(self.out, LeaveAlternateScreen, cursor::Show);
+    }
+}
@@ -46,8 +46,8 @@ fn list_modes_flag() -> String {
     return modes_msg;
 }
 
-fn list_dicts_flag() -> String {
-
+// Names of the dictionaries at the config directory, sorted
+pub fn all_dictionaries() -> Vec<String> {
     let mut all_dicts : HashSet<String> = HashSet::new();
 
     all_dicts.extend(file_names_in_dir(&dictionaries_path()).unwrap_or_default());
@@ -55,6 +55,12 @@ fn list_dicts_flag() -> String {
     let mut all_dicts : Vec<String> = all_dicts.into_iter().collect();
 
     all_dicts.sort();
+    return all_dicts;
+}
+
+fn list_dicts_flag() -> String {
+
+    let all_dicts = all_dictionaries();
 
     let mut list_dicts_msg = String::new();
 
@@ -88,19 +94,54 @@ fn file_names_in_dir(path : &PathBuf) -> io::Result<HashSet<String>> {
 fn help_flag() -> &'static str {
 "Mecano, a typing train
 
-Usage: mecano [OPTIONS] [FLAGS]
+Usage: mecano [OPTIONS] [FLAGS] [-]
+       mecano stats [STATS OPTIONS]
 
 OPTIONS:
--f, --file <FILE>           Plays using the chosen file or dictionary
+-c, --command <CMD>         Plays using the output of the chosen command
+-f, --file <FILE>           Plays using the chosen file or dictionary. Use - to read the text from stdin, or a,b or a:3,b:1 to mix dictionaries
+-l, --layout <LAYOUT>       Keyboard layout of the results heatmap: qwerty, dvorak, colemak or azerty
 -m, --mode <MODE>           Plays the chosen mode
--r, --rate <RATE>           Plays with the chosen rate. This affects time measures accuracy. The higher the better.
+-n, --top <N>               Plays only the N most frequent words of the dictionary
+-q, --quote <LENGTH>        Plays a whole quote of the chosen length: short, medium, long or any
+-s, --selection <SELECTION> Draws dictionary words: uniform, shuffle-bag (every word before any repeats) or no-repeat
 -t, --time <SECS>           Choose the game time in seconds
+-w, --words <WORDS>         Plays a test that ends after typing the chosen number of words
+    --seed <N>              Plays the same words every time with the same seed
+    --capitals <PCT>        Capitalises the chosen percentage of words
+    --punctuation <PCT>     Adds commas, periods, question marks... after the chosen percentage of words
+    --brackets <PCT>        Wraps the chosen percentage of words in quotes or brackets
+    --numbers <PCT>         Inserts a number before the chosen percentage of words
+    --ngram <N>             Chars of the n-grams pseudo mode learns from, 3 by default
+    --lesson <N>            Plays the chosen lesson in lessons mode, if unlocked
+    --min-length <N>        Plays only words of at least N chars
+    --max-length <N>        Plays only words of at most N chars
+    --only <CHARS>          Plays only words made of the chosen letters, like asdfghjkl for the home row
+    --contains <CHARS>      Plays only words with at least one of the chosen letters
+    --pattern <REGEX>       Plays only words matching the regular expression
+    --wikipedia <PATH>      Plays a random article of a Wikipedia dump (.xml or .xml.bz2) or extracted articles directory
+    --article <TITLE>       Plays the chosen article in wikipedia mode
 
 FLAGS:
 -h, --help                  Print help
 -v, --version               Print version 
     --list-dictionaries     List all dicitonaries. You can add more at ~/.config/mecano/dictionaries
-    --list-modes            List all available modes"
+    --list-modes            List all available modes
+    --daily                 Plays with the seed of today, the same words for everyone on the same day
+
+KEYS:
+Tab                         Starts a new test with the same options, on the results
+Shift+Tab                   Retries the same words, on the results
+m                           Switches mode, on the results
+d                           Switches dictionary, on the results
+Esc                         Quits
+
+STATS OPTIONS:
+-m, --mode <MODE>           Only tests played in the chosen mode
+-f, --file <FILE>           Only tests played with the chosen file or dictionary
+-t, --time <SECS>           Only time tests of the chosen seconds
+-w, --words <WORDS>         Only words tests of the chosen number of words
+    --json                  Print the summary as JSON"
 }
 
 
@@ -2,17 +2,38 @@ use std::path::PathBuf;
 
 pub mod flags;
 pub mod options;
+pub mod stats;
 
 pub fn dictionaries_path() -> PathBuf {
     let resources_path = root_config_path().join("dictionaries");
     return resources_path;
 }
 
+pub fn quotes_path() -> PathBuf {
+    let quotes_path = root_config_path().join("quotes");
+    return quotes_path;
+}
+
 pub fn config_file_path() -> PathBuf {
     let resources_path = root_config_path().join("config.toml");
     return resources_path;
 }
 
+pub fn history_file_path() -> PathBuf {
+    let history_path = root_config_path().join("history.jsonl");
+    return history_path;
+}
+
+pub fn adaptive_profile_path() -> PathBuf {
+    let profile_path = root_config_path().join("adaptive.json");
+    return profile_path;
+}
+
+pub fn lessons_progress_path() -> PathBuf {
+    let progress_path = root_config_path().join("lessons.json");
+    return progress_path;
+}
+
 fn root_config_path() -> PathBuf {
     let root_config_path = dirs::config_dir()
         .expect("config directory not found")
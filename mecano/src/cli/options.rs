@@ -1,25 +1,55 @@
-use std::io;
+use std::io::{self, IsTerminal, Read};
 
 use crate::config::fields::FieldError;
-use crate::{Count, TermUnit};
+use crate::Count;
 use crate::config::Config;
+use crate::mode::TEXT_MODES;
 
 use super::config_file_path;
 
+const STDIN_ARG : &str = "-";
+const DAILY_ARG : &str = "--daily";
+const REMOVED_ARGS : [&str; 2] = ["-r", "--rate"];
+
 pub fn config_with_args(args : &Vec<String>) -> io::Result<Config> {
     let mut config; 
     let config_file = config_file_path();
 
-    if let Ok(c) = Config::from_path(&config_file_path()) {
-        config = c;
-    } else {
+    match Config::from_path(&config_file_path()) {
+        Ok(c) => config = c,
+        Err(e) => {
+            let config_file_display = config_file.display();
+            let error_msg = format!("invalid configuration in \"{config_file_display}\": {e}");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+        },
+    }
+    if config.has_rate() {
         let config_file_display = config_file.display();
-        let error_msg = format!("invalid configuration in \"{config_file_display}\"");
-        return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+        eprintln!("\"rate\" in \"{config_file_display}\" is obsolete and ignored, you can remove it");
     }
 
+    // Options choosing what to type, stdin isn't read on its own after them
+    let mut mode_chosen = false;
+    let mut file_chosen = false;
+
     let mut args_iter = args.iter().skip(1);
     while let Some(item) = args_iter.next() {
+        if item == STDIN_ARG {
+            use_stdin(&mut config, mode_chosen)?;
+            continue;
+        }
+
+        if item == DAILY_ARG {
+            config.set_seed(daily_seed());
+            continue;
+        }
+
+        // The clock doesn't depend on an update rate anymore
+        if REMOVED_ARGS.contains(&item.as_str()) {
+            let error_msg = format!("Option \"{item}\" was removed, time is measured from the keys themselves");
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+        }
+
         let opt;
         if let Some(arg) = args_iter.next() {
             opt = arg;
@@ -27,10 +57,44 @@ pub fn config_with_args(args : &Vec<String>) -> io::Result<Config> {
             match item.as_str() {
                 "-m" | "--mode" => {
                     err = config.set_mode(opt);
+                    mode_chosen = true;
                 }
 
                 "-f" | "--file" => {
-                    err = config.set_file(opt);
+                    if opt == STDIN_ARG {
+                        use_stdin(&mut config, mode_chosen)?;
+                    } else {
+                        err = config.set_file(opt);
+                    }
+                    file_chosen = true;
+                }
+
+                "-c" | "--command" => {
+                    err = config.set_command(opt);
+                    if err.is_none() {
+                        err = config.set_mode("command");
+                    }
+                    mode_chosen = true;
+                }
+
+                "--wikipedia" => {
+                    err = config.set_wikipedia(opt);
+                    if err.is_none() {
+                        err = config.set_mode("wikipedia");
+                    }
+                    mode_chosen = true;
+                }
+
+                "-q" | "--quote" => {
+                    err = config.set_quote_length(opt);
+                    if err.is_none() {
+                        err = config.set_mode("quote");
+                    }
+                    mode_chosen = true;
+                }
+
+                "--article" => {
+                    config.set_article(opt);
                 }
 
                 "-t" | "--time" => {
@@ -38,18 +102,112 @@ pub fn config_with_args(args : &Vec<String>) -> io::Result<Config> {
                     if let Err(_) = time {
                         err = Some(FieldError::NotAPositiveNumber);
                     } else {
-                        config.set_max_time(time.unwrap());
+                        err = config.set_max_time(time.unwrap());
+                        config.unset_words();
                     }
                 }
 
-                "-r" | "--rate" => {
-                    let rate = opt.parse::<TermUnit>();
+                "-l" | "--layout" => {
+                    err = config.set_layout(opt);
+                }
+
+                "-w" | "--words" => {
+                    let words = opt.parse::<Count>();
+                    if let Err(_) = words {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        err = config.set_words(words.unwrap());
+                    }
+                }
+
+                "--capitals" | "--punctuation" | "--brackets" | "--numbers" => {
+                    let rate = opt.parse::<Count>();
                     if let Err(_) = rate {
                         err = Some(FieldError::NotAPositiveNumber);
                     } else {
-                        config.set_rate(rate.unwrap());
+                        let rate = rate.unwrap();
+                        err = match item.as_str() {
+                            "--capitals" => config.set_capitals(rate),
+                            "--punctuation" => config.set_punctuation(rate),
+                            "--brackets" => config.set_brackets(rate),
+                            _ => config.set_numbers(rate),
+                        };
                     }
-                },
+                }
+
+                "-n" | "--top" => {
+                    let top = opt.parse::<Count>();
+                    if let Err(_) = top {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        err = config.set_top(top.unwrap());
+                    }
+                }
+
+                "--ngram" => {
+                    let ngram = opt.parse::<Count>();
+                    if let Err(_) = ngram {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        err = config.set_ngram(ngram.unwrap());
+                    }
+                }
+
+                "--lesson" => {
+                    let lesson = opt.parse::<Count>();
+                    if let Err(_) = lesson {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        err = config.set_lesson(lesson.unwrap());
+                        if err.is_none() {
+                            err = config.set_mode("lessons");
+                        }
+                        mode_chosen = true;
+                    }
+                }
+
+                "--min-length" => {
+                    let min = opt.parse::<Count>();
+                    if let Err(_) = min {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        config.set_min_length(min.unwrap());
+                    }
+                }
+
+                "--max-length" => {
+                    let max = opt.parse::<Count>();
+                    if let Err(_) = max {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        err = config.set_max_length(max.unwrap());
+                    }
+                }
+
+                "--only" => {
+                    config.set_only(opt);
+                }
+
+                "--contains" => {
+                    config.set_contains(opt);
+                }
+
+                "--pattern" => {
+                    err = config.set_pattern(opt);
+                }
+
+                "-s" | "--selection" => {
+                    err = config.set_selection(opt);
+                }
+
+                "--seed" => {
+                    let seed = opt.parse::<u64>();
+                    if let Err(_) = seed {
+                        err = Some(FieldError::NotAPositiveNumber);
+                    } else {
+                        config.set_seed(seed.unwrap());
+                    }
+                }
 
                 _ => (),
             }
@@ -67,5 +225,47 @@ pub fn config_with_args(args : &Vec<String>) -> io::Result<Config> {
             return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
         }
     }
+
+    // `cat notes.txt | mecano` works as `mecano -`, unless something else
+    // to type was chosen, as scripts and editors may leave stdin open
+    if config.get_text().is_none() && !mode_chosen && !file_chosen
+        && !io::stdin().is_terminal() {
+        use_stdin(&mut config, mode_chosen)?;
+    }
+
+    // Other modes would drop the text without a word
+    let mode = config.get_mode();
+    if config.get_text().is_some() && !TEXT_MODES.contains(&mode.as_str()) {
+        let error_msg = format!("{mode} mode doesn't take text from stdin, only {} do", TEXT_MODES.join(" and "));
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+    }
+
     return Ok(config);
 }
+
+// The local date as a number, like 20261018, so everyone gets the same
+// words on the same day
+fn daily_seed() -> u64 {
+    let today = chrono::Local::now().date_naive();
+    let seed = today.format("%Y%m%d").to_string();
+    return seed.parse().unwrap_or_default();
+}
+
+// Reads the whole pipe before the game takes the terminal. Keys are then
+// read from the terminal itself
+fn use_stdin(config : &mut Config, mode_chosen : bool) -> io::Result<()> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)?;
+    if text.trim().is_empty() {
+        let error_msg = "no text to type read from stdin";
+        return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+    }
+
+    config.set_text(&text);
+    // A mode given in the command line is kept, code mode also takes its
+    // text from stdin
+    if !mode_chosen && config.get_mode() != "code" {
+        config.set_mode("file");
+    }
+    return Ok(());
+}
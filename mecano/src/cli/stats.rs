@@ -0,0 +1,321 @@
+use std::{collections::BTreeMap, fmt::Display, io};
+
+use serde::Serialize;
+
+use crate::{history::{self, Record}, Count};
+
+use super::history_file_path;
+
+const SHORT_WINDOW : usize = 10;
+const LONG_WINDOW : usize = 100;
+// WPM difference under which the trend is considered steady
+const TREND_THRESHOLD : f64 = 1.0;
+
+#[derive(Debug, Default)]
+pub struct StatsFilter {
+    mode : Option<String>,
+    file : Option<String>,
+    time : Option<Count>,
+    words : Option<Count>,
+}
+
+impl StatsFilter {
+    fn matches(&self, record : &Record) -> bool {
+        if let Some(mode) = &self.mode {
+            if *mode != record.mode { return false; }
+        }
+        if let Some(file) = &self.file {
+            if file != record.source() { return false; }
+        }
+        if let Some(time) = self.time {
+            if record.kind != "time" || record.target != time { return false; }
+        }
+        if let Some(words) = self.words {
+            if record.kind != "words" || record.target != words { return false; }
+        }
+        return true;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Average {
+    pub tests : usize,
+    pub wpm : f64,
+    pub raw : f64,
+    pub acc : f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Best {
+    pub wpm : f64,
+    pub acc : f64,
+    pub timestamp : u64,
+    pub file : String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileSummary {
+    pub file : String,
+    pub best_wpm : f64,
+    pub average : Average,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Up,
+    Down,
+    Steady,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub tests : usize,
+    pub best : Option<Best>,
+    pub last_10 : Option<Average>,
+    pub last_100 : Option<Average>,
+    pub all : Option<Average>,
+    pub files : Vec<FileSummary>,
+    pub trend : Trend,
+}
+
+// Records are expected in the order they were written
+pub fn summarize(records : &[Record], filter : &StatsFilter) -> Summary {
+    let records : Vec<&Record> = records.iter()
+        .filter(|r| filter.matches(r))
+        .collect();
+
+    let best = records.iter()
+        .max_by(|a, b| a.wpm.total_cmp(&b.wpm))
+        .map(|r| Best {
+            wpm : r.wpm,
+            acc : r.acc,
+            timestamp : r.timestamp,
+            file : r.source().to_string(),
+        });
+
+    let mut by_file : BTreeMap<&str, Vec<&Record>> = BTreeMap::new();
+    for record in &records {
+        by_file.entry(record.source()).or_default().push(record);
+    }
+
+    let files = by_file.iter()
+        .map(|(file, records)| FileSummary {
+            file : file.to_string(),
+            best_wpm : records.iter().map(|r| r.wpm).fold(0.0, f64::max),
+            average : average(records).unwrap(),
+        })
+        .collect();
+
+    return Summary {
+        tests : records.len(),
+        best,
+        last_10 : average(last(&records, SHORT_WINDOW)),
+        last_100 : average(last(&records, LONG_WINDOW)),
+        all : average(&records),
+        files,
+        trend : trend(&records),
+    }
+}
+
+fn last<'a, 'b>(records : &'a [&'b Record], n : usize) -> &'a [&'b Record] {
+    return &records[records.len() - n.min(records.len())..];
+}
+
+fn average(records : &[&Record]) -> Option<Average> {
+    if records.is_empty() {
+        return None;
+    }
+    let n = records.len() as f64;
+    return Some(Average {
+        tests : records.len(),
+        wpm : records.iter().map(|r| r.wpm).sum::<f64>() / n,
+        raw : records.iter().map(|r| r.raw).sum::<f64>() / n,
+        acc : records.iter().map(|r| r.acc).sum::<f64>() / n,
+    });
+}
+
+// Compares the last tests against the same amount of tests before them
+fn trend(records : &[&Record]) -> Trend {
+    let window = SHORT_WINDOW.min(records.len() / 2);
+    if window == 0 {
+        return Trend::Unknown;
+    }
+    let recent = last(records, window);
+    let previous = last(&records[..records.len() - window], window);
+    let diff = average(recent).unwrap().wpm - average(previous).unwrap().wpm;
+    if diff > TREND_THRESHOLD {
+        return Trend::Up;
+    } else if diff < -TREND_THRESHOLD {
+        return Trend::Down;
+    } else {
+        return Trend::Steady;
+    }
+}
+
+impl Display for Trend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let trend = match self {
+            Trend::Up => "up",
+            Trend::Down => "down",
+            Trend::Steady => "steady",
+            Trend::Unknown => "not enough tests",
+        };
+        return write!(f, "{trend}");
+    }
+}
+
+fn average_row(f : &mut std::fmt::Formatter<'_>, name : &str,
+    average : &Option<Average>) -> std::fmt::Result {
+    if let Some(a) = average {
+        let acc = a.acc * 100.0;
+        return writeln!(f, "{name:<12}{:>7}{:>9.2}{:>9.2}{acc:>8.2}%",
+            a.tests, a.wpm, a.raw);
+    }
+    return Ok(());
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tests == 0 {
+            return write!(f, "No tests recorded");
+        }
+
+        if let Some(best) = &self.best {
+            let acc = best.acc * 100.0;
+            writeln!(f, "BEST   {:.2} WPM  {acc:.2}% ACC  ({})\n", best.wpm, best.file)?;
+        }
+
+        writeln!(f, "{:<12}{:>7}{:>9}{:>9}{:>9}", "", "TESTS", "WPM", "RAW", "ACC")?;
+        average_row(f, "last 10", &self.last_10)?;
+        average_row(f, "last 100", &self.last_100)?;
+        average_row(f, "all", &self.all)?;
+
+        writeln!(f, "\n{:<20}{:>7}{:>9}{:>9}{:>9}", "FILE", "TESTS", "BEST", "WPM", "ACC")?;
+        for file in &self.files {
+            let acc = file.average.acc * 100.0;
+            writeln!(f, "{:<20}{:>7}{:>9.2}{:>9.2}{acc:>8.2}%",
+                file.file, file.average.tests, file.best_wpm, file.average.wpm)?;
+        }
+
+        let trend = self.trend;
+        return write!(f, "\nTREND  {trend}");
+    }
+}
+
+// Parses `mecano stats [OPTIONS]` and returns the message to print
+pub fn stats(args : &Vec<String>) -> io::Result<String> {
+    let mut filter = StatsFilter::default();
+    let mut json = false;
+
+    let mut args_iter = args.iter().skip(2);
+    while let Some(item) = args_iter.next() {
+        if item == "--json" {
+            json = true;
+            continue;
+        }
+
+        let opt = args_iter.next().ok_or_else(|| {
+            let error_msg = format!("Missing argument for option \"{item}\"");
+            io::Error::new(io::ErrorKind::InvalidInput, error_msg)
+        })?;
+
+        match item.as_str() {
+            "-m" | "--mode" => filter.mode = Some(opt.clone()),
+            "-f" | "--file" => filter.file = Some(opt.clone()),
+            "-t" | "--time" => filter.time = Some(parse_count(item, opt)?),
+            "-w" | "--words" => filter.words = Some(parse_count(item, opt)?),
+            _ => {
+                let error_msg = format!("Unknown option \"{item}\" for stats");
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+            }
+        }
+    }
+
+    let records = history::read(&history_file_path())?;
+    let summary = summarize(&records, &filter);
+
+    if json {
+        return Ok(serde_json::to_string_pretty(&summary)?);
+    } else {
+        return Ok(summary.to_string());
+    }
+}
+
+fn parse_count(item : &str, opt : &str) -> io::Result<Count> {
+    return opt.parse::<Count>().map_err(|_| {
+        let error_msg = format!("Error in option \"{item}\": \"{opt}\" invalid or negative number");
+        io::Error::new(io::ErrorKind::InvalidInput, error_msg)
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{history::{Record, VERSION}, mode::transform::Transforms};
+
+    use super::{summarize, StatsFilter, Trend};
+
+    fn record(file : &str, target : u64, wpm : f64) -> Record {
+        return Record {
+            version : VERSION,
+            timestamp : 0,
+            mode : String::from("dictionary"),
+            file : file.to_string(),
+            title : None,
+            kind : String::from("time"),
+            target,
+            duration : target as f64,
+            wpm,
+            raw : wpm,
+            acc : 1.0,
+            right : 0,
+            wrong : 0,
+            extra : 0,
+            missed : 0,
+            transforms : Transforms::default(),
+            seed : None,
+        }
+    }
+
+    #[test]
+    fn summary() {
+        let mut records = Vec::new();
+        for i in 0..20 {
+            records.push(record("100_english", 60, 40.0 + i as f64));
+        }
+        records.push(record("100_spanish", 30, 100.0));
+
+        let summary = summarize(&records, &StatsFilter::default());
+        assert_eq!(summary.tests, 21);
+        assert_eq!(summary.best.unwrap().file, "100_spanish");
+        assert_eq!(summary.last_10.unwrap().tests, 10);
+        assert_eq!(summary.last_100.unwrap().tests, 21);
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!(summary.files[0].best_wpm, 59.0);
+        assert_eq!(summary.trend, Trend::Up);
+
+        let filter = StatsFilter { time : Some(60), ..Default::default() };
+        let summary = summarize(&records, &filter);
+        assert_eq!(summary.tests, 20);
+        assert_eq!(summary.best.unwrap().wpm, 59.0);
+        assert_eq!(summary.all.unwrap().wpm, 49.5);
+
+        // Quotes are grouped by where they come from, not by their author
+        let mut quotes = vec![record("quotes", 60, 30.0), record("quotes", 60, 35.0)];
+        quotes[0].mode = String::from("quote");
+        quotes[0].title = Some(String::from("Ada Lovelace"));
+        quotes[1].mode = String::from("quote");
+        quotes[1].title = Some(String::from("Alan Turing"));
+        let filter = StatsFilter { file : Some(String::from("quotes")), ..Default::default() };
+        let summary = summarize(&quotes, &filter);
+        assert_eq!(summary.tests, 2);
+        assert_eq!(summary.files.len(), 1);
+        assert_eq!(summary.best.unwrap().file, "quotes");
+
+        let filter = StatsFilter { file : Some(String::from("none")), ..Default::default() };
+        let summary = summarize(&records, &filter);
+        assert_eq!(summary.tests, 0);
+        assert_eq!(summary.trend, Trend::Unknown);
+    }
+}
@@ -2,14 +2,25 @@ use std::path::PathBuf;
 
 use serde::{de::Visitor, Deserialize};
 
-use crate::{mode::{all_modes_str, ALL_MODES}, path_to_file};
+use crate::{keyboard::ALL_LAYOUTS, mode::{all_modes_str, dictionary::ALL_SELECTIONS, quote::ALL_QUOTE_LENGTHS, ALL_MODES}, path_to_file, Count};
+
+// Separators of dictionary mixes like "100_english:3,100_italian:1"
+const MIX_SEPARATOR : char = ',';
+const RATIO_SEPARATOR : char = ':';
 
 #[derive(Debug)]
 pub enum FieldError {
     InvalidMode,
     InvalidFile,
+    InvalidMix,
+    InvalidLayout,
+    InvalidQuoteLength,
+    InvalidSelection,
+    InvalidPattern,
+    EmptyCommand,
     ZeroNotAllowed,
     NotAPositiveNumber,
+    NotAPercentage,
 }
 
 impl FieldError {
@@ -17,8 +28,15 @@ impl FieldError {
         match self {
             FieldError::InvalidMode => "invalid mode",
             FieldError::InvalidFile => "invalid file",
+            FieldError::InvalidMix => "invalid dictionary mix",
+            FieldError::InvalidLayout => "invalid layout",
+            FieldError::InvalidQuoteLength => "invalid quote length",
+            FieldError::InvalidSelection => "invalid selection",
+            FieldError::InvalidPattern => "invalid pattern",
+            FieldError::EmptyCommand => "empty command",
             FieldError::ZeroNotAllowed => "zero not allowed",
             FieldError::NotAPositiveNumber => "invalid or negative number",
+            FieldError::NotAPercentage => "not a percentage",
         }.to_string()
     }
 
@@ -27,8 +45,15 @@ impl FieldError {
         match self {
             FieldError::InvalidMode => format!("a valid mode among: {all_modes}"),
             FieldError::InvalidFile => String::from("a valid file"),
+            FieldError::InvalidMix => String::from("dictionaries with ratios like 100_english:3,100_italian:1"),
+            FieldError::EmptyCommand => String::from("a command to run"),
+            FieldError::InvalidLayout => format!("a layout among: {}", ALL_LAYOUTS.join(", ")),
+            FieldError::InvalidQuoteLength => format!("a length among: {}", ALL_QUOTE_LENGTHS.join(", ")),
+            FieldError::InvalidSelection => format!("a selection among: {}", ALL_SELECTIONS.join(", ")),
+            FieldError::InvalidPattern => String::from("a valid regular expression"),
             FieldError::ZeroNotAllowed => String::from("a number greater than 0"),
-            FieldError::NotAPositiveNumber => String::from("a valid positive number")
+            FieldError::NotAPositiveNumber => String::from("a valid positive number"),
+            FieldError::NotAPercentage => String::from("a number from 0 to 100"),
         }
     }
 }
@@ -90,13 +115,18 @@ impl<'de> Visitor<'de> for ModeFieldVisitor {
 #[derive(Debug, Clone)]
 pub struct FileField {
     field : PathBuf,
+    // Dictionaries and their ratios, empty for a single file
+    mix : Vec<(PathBuf, Count)>,
 }
 
 impl FileField {
     pub fn new(s : &str) -> Result<FileField, FieldError> { 
         let result = path_to_file(s);
         if let Ok(path) = result {
-            return Ok(FileField{ field : path });
+            return Ok(FileField{ field : path, mix : Vec::new() });
+        } else if s.contains([MIX_SEPARATOR, RATIO_SEPARATOR]) {
+            let mix = parse_mix(s)?;
+            return Ok(FileField{ field : mix[0].0.clone(), mix });
         } else {
             dbg!("Failed FileField::new()");
             let _ = result.inspect_err(|e| eprintln!("{}", e.kind()));
@@ -108,6 +138,29 @@ impl FileField {
         return &self.field;
 
     }
+
+    pub fn get_mix(&self) -> &Vec<(PathBuf, Count)> {
+        return &self.mix;
+    }
+}
+
+// Dictionaries without a ratio weigh 1
+fn parse_mix(s : &str) -> Result<Vec<(PathBuf, Count)>, FieldError> {
+    let mut mix = Vec::new();
+    for part in s.split(MIX_SEPARATOR).map(str::trim) {
+        let mut name = part;
+        let mut ratio = 1;
+        if let Some((n, r)) = part.rsplit_once(RATIO_SEPARATOR) {
+            name = n.trim();
+            ratio = r.trim().parse::<Count>().map_err(|_| FieldError::InvalidMix)?;
+            if ratio == 0 {
+                return Err(FieldError::InvalidMix);
+            }
+        }
+        let path = path_to_file(name).map_err(|_| FieldError::InvalidFile)?;
+        mix.push((path, ratio));
+    }
+    return Ok(mix);
 }
 
 impl<'de> Deserialize<'de> for FileField {
@@ -144,3 +197,27 @@ impl<'de> Visitor<'de> for FileFieldVisitor {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::path_to_file;
+
+    use super::{FieldError, FileField};
+
+    #[test]
+    fn dictionary_mix() {
+        let single = FileField::new("100_english").unwrap();
+        assert!(single.get_mix().is_empty());
+
+        let mix = FileField::new("100_english:3, 100_italian").unwrap();
+        assert_eq!(mix.get_pathbuf(), &path_to_file("100_english").unwrap());
+        assert_eq!(mix.get_mix(), &vec![
+            (path_to_file("100_english").unwrap(), 3),
+            (path_to_file("100_italian").unwrap(), 1),
+        ]);
+
+        assert!(matches!(FileField::new("100_english:0,100_italian"), Err(FieldError::InvalidMix)));
+        assert!(matches!(FileField::new("100_english:x"), Err(FieldError::InvalidMix)));
+        assert!(matches!(FileField::new("100_english,missing"), Err(FieldError::InvalidFile)));
+    }
+}
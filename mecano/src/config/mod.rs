@@ -4,6 +4,8 @@ use std::io;
 use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::keyboard::{HeatmapMetric, Layout};
+use crate::mode::{dictionary::Selection, quote::QuoteLength, transform::Transforms};
 use crate::Count;
 
 use self::fields::FieldError;
@@ -21,7 +23,19 @@ const FILE : &'static str = "100_english";
 const WIDTH : TermUnit = 80;
 const MAX_TIME : Count = 60;
 const LENGHT : TermUnit = 2;
-const RATE : u16 = 1000;
+const LAYOUT : Layout = Layout::Qwerty;
+const HEATMAP : HeatmapMetric = HeatmapMetric::Errors;
+const QUOTE_LENGTH : QuoteLength = QuoteLength::Any;
+const SELECTION : Selection = Selection::Uniform;
+const NGRAM : Count = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestKind {
+    Time(Duration),
+    Words(Count),
+    // Ends on the last char of the last word
+    Passage(Count),
+}
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
 pub struct Theme {
@@ -73,7 +87,39 @@ pub struct Config {
     theme : Option<Theme>,
     mode : Option<ModeField>,
     file : Option<FileField>,
-    rate : Option<u16>,
+    words : Option<Count>,
+    layout : Option<Layout>,
+    heatmap : Option<HeatmapMetric>,
+    command : Option<String>,
+    wikipedia : Option<PathBuf>,
+    article : Option<String>,
+    quote_length : Option<QuoteLength>,
+    // Percentage of words changed by each transform
+    capitals : Option<Count>,
+    punctuation : Option<Count>,
+    brackets : Option<Count>,
+    numbers : Option<Count>,
+    // Only the most frequent words of the dictionary
+    top : Option<Count>,
+    // Chars of the n-grams learnt in pseudo mode
+    ngram : Option<Count>,
+    // Lesson played in lessons mode, the last one unlocked when missing
+    lesson : Option<Count>,
+    // Only the words passing these filters are played
+    min_length : Option<Count>,
+    max_length : Option<Count>,
+    only : Option<String>,
+    contains : Option<String>,
+    pattern : Option<String>,
+    // How the words of the dictionary are drawn
+    selection : Option<Selection>,
+    // Same seed, same words
+    seed : Option<u64>,
+    // Text read from stdin, never from the config file
+    #[serde(skip)]
+    text : Option<String>,
+    // Obsolete, read only to warn about it
+    rate : Option<toml::Value>,
 }
 
 impl Config {
@@ -85,6 +131,28 @@ impl Config {
             lenght : None,
             mode : None,
             file : None,
+            words : None,
+            layout : None,
+            heatmap : None,
+            command : None,
+            wikipedia : None,
+            article : None,
+            quote_length : None,
+            capitals : None,
+            punctuation : None,
+            brackets : None,
+            numbers : None,
+            top : None,
+            ngram : None,
+            lesson : None,
+            min_length : None,
+            max_length : None,
+            only : None,
+            contains : None,
+            pattern : None,
+            selection : None,
+            seed : None,
+            text : None,
             rate : None,
         }
     }
@@ -97,7 +165,29 @@ impl Config {
             lenght : Some(LENGHT),
             mode : Some(ModeField::new(MODE).expect(&format!("Default mode \"{MODE}\" failed to build"))),
             file : Some(FileField::new(FILE).expect(&format!("Default file \"{FILE}\" failed to build"))),
-            rate : Some(RATE),
+            words : None,
+            layout : Some(LAYOUT),
+            heatmap : Some(HEATMAP),
+            command : None,
+            wikipedia : None,
+            article : None,
+            quote_length : None,
+            capitals : None,
+            punctuation : None,
+            brackets : None,
+            numbers : None,
+            top : None,
+            ngram : None,
+            lesson : None,
+            min_length : None,
+            max_length : None,
+            only : None,
+            contains : None,
+            pattern : None,
+            selection : None,
+            seed : None,
+            text : None,
+            rate : None,
         }
     }
 
@@ -109,7 +199,29 @@ impl Config {
             lenght : Some(LENGHT),
             mode : Some(ModeField::new(TEST_MODE).expect(&format!("Default mode \"{TEST_MODE}\" failed to build"))),
             file : Some(FileField::new(FILE).expect(&format!("Default file \"{FILE}\" failed to build"))),
-            rate : Some(RATE),
+            words : None,
+            layout : Some(LAYOUT),
+            heatmap : Some(HEATMAP),
+            command : None,
+            wikipedia : None,
+            article : None,
+            quote_length : None,
+            capitals : None,
+            punctuation : None,
+            brackets : None,
+            numbers : None,
+            top : None,
+            ngram : None,
+            lesson : None,
+            min_length : None,
+            max_length : None,
+            only : None,
+            contains : None,
+            pattern : None,
+            selection : None,
+            seed : None,
+            text : None,
+            rate : None,
         }
     }
 
@@ -121,7 +233,29 @@ impl Config {
             lenght : Some(LENGHT),
             mode : Some(ModeField::new(MAX_TIME_MODE).expect(&format!("Default mode \"{MAX_TIME_MODE}\" failed to build"))),
             file : Some(FileField::new(FILE).expect(&format!("Default file \"{FILE}\" failed to build"))),
-            rate : Some(RATE),
+            words : None,
+            layout : Some(LAYOUT),
+            heatmap : Some(HEATMAP),
+            command : None,
+            wikipedia : None,
+            article : None,
+            quote_length : None,
+            capitals : None,
+            punctuation : None,
+            brackets : None,
+            numbers : None,
+            top : None,
+            ngram : None,
+            lesson : None,
+            min_length : None,
+            max_length : None,
+            only : None,
+            contains : None,
+            pattern : None,
+            selection : None,
+            seed : None,
+            text : None,
+            rate : None,
         }
     }
 
@@ -135,6 +269,20 @@ impl Config {
         // let mut error_msg = String::new();
         let config = toml::from_str::<Config>(&string);
         if let Ok(config) = config {
+            // Serde only checks the type, the range is checked here
+            let percentages = [
+                ("capitals", config.capitals),
+                ("punctuation", config.punctuation),
+                ("brackets", config.brackets),
+                ("numbers", config.numbers),
+            ];
+            for (name, percentage) in percentages {
+                if percentage.is_some_and(|p| p > 100) {
+                    let error = FieldError::NotAPercentage;
+                    let error_msg = format!("{name}: {}, expected {}", error.error_msg(), error.expecting());
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+                }
+            }
             return Ok(config);
         } else {
             let e = config.unwrap_err();
@@ -144,6 +292,11 @@ impl Config {
         }
     }
 
+    // The clock used to be updated at this rate, it's ignored now
+    pub fn has_rate(&self) -> bool {
+        return self.rate.is_some();
+    }
+
     pub fn get_mode(&self) -> String { 
         if let Some(mode) = &self.mode {
             return mode.to_string();
@@ -172,12 +325,21 @@ impl Config {
 
     pub fn set_file(&mut self, f : &str) -> Option<FieldError> { 
         let file = FileField::new(f);
-        if let Ok(file) = file {
-            self.file = Some(file);
-            return None;
-        } else {
-            return Some(FieldError::InvalidFile);
+        match file {
+            Ok(file) => {
+                self.file = Some(file);
+                return None;
+            }
+            Err(e) => return Some(e),
+        }
+    }
+
+    // Dictionaries mixed in one session, empty for a single file
+    pub fn get_mix(&self) -> Vec<(PathBuf, Count)> {
+        if let Some(file) = &self.file {
+            return file.get_mix().clone();
         }
+        return Vec::new();
     }
 
     pub fn get_width(&self) -> TermUnit { 
@@ -227,21 +389,223 @@ impl Config {
         return None;
     }
 
-    pub fn get_rate(&self) -> u16 {
-        if let Some(rate) = &self.rate {
-            return *rate;
+    pub fn get_words(&self) -> Option<Count> {
+        return self.words;
+    }
+    pub fn set_words(&mut self, w : Count) -> Option<FieldError> {
+        if w < 1 {
+            return Some(FieldError::ZeroNotAllowed)
+        }
+        self.words = Some(w);
+        return None;
+    }
+    pub fn unset_words(&mut self) {
+        self.words = None;
+    }
+
+    pub fn get_test_kind(&self) -> TestKind {
+        if let Some(words) = self.get_words() {
+            return TestKind::Words(words);
+        } else {
+            return TestKind::Time(self.get_max_time());
+        }
+    }
+
+    pub fn get_layout(&self) -> Layout {
+        return self.layout.unwrap_or(LAYOUT);
+    }
+    pub fn set_layout(&mut self, l : &str) -> Option<FieldError> {
+        if let Some(layout) = Layout::new(l) {
+            self.layout = Some(layout);
+            return None;
         } else {
-            return Config::default().rate.unwrap();
+            return Some(FieldError::InvalidLayout);
+        }
+    }
+
+    pub fn get_heatmap(&self) -> HeatmapMetric {
+        return self.heatmap.unwrap_or(HEATMAP);
+    }
+
+    pub fn get_command(&self) -> Option<String> {
+        return self.command.clone();
+    }
+    pub fn set_command(&mut self, c : &str) -> Option<FieldError> {
+        if c.trim().is_empty() {
+            return Some(FieldError::EmptyCommand);
+        }
+        self.command = Some(c.to_string());
+        return None;
+    }
+
+    pub fn get_wikipedia(&self) -> Option<PathBuf> {
+        return self.wikipedia.clone();
+    }
+    pub fn set_wikipedia(&mut self, w : &str) -> Option<FieldError> {
+        let path = PathBuf::from(w);
+        if !path.exists() {
+            return Some(FieldError::InvalidFile);
+        }
+        self.wikipedia = Some(path);
+        return None;
+    }
+
+    pub fn get_article(&self) -> Option<String> {
+        return self.article.clone();
+    }
+    pub fn set_article(&mut self, a : &str) {
+        self.article = Some(a.to_string());
+    }
+
+    pub fn get_quote_length(&self) -> QuoteLength {
+        return self.quote_length.unwrap_or(QUOTE_LENGTH);
+    }
+    pub fn set_quote_length(&mut self, q : &str) -> Option<FieldError> {
+        if let Some(quote_length) = QuoteLength::new(q) {
+            self.quote_length = Some(quote_length);
+            return None;
+        }
+        return Some(FieldError::InvalidQuoteLength);
+    }
+
+    pub fn get_transforms(&self) -> Transforms {
+        return Transforms {
+            capitals : self.capitals.unwrap_or(0),
+            punctuation : self.punctuation.unwrap_or(0),
+            brackets : self.brackets.unwrap_or(0),
+            numbers : self.numbers.unwrap_or(0),
+        }
+    }
+    pub fn set_capitals(&mut self, c : Count) -> Option<FieldError> {
+        if c > 100 {
+            return Some(FieldError::NotAPercentage);
+        }
+        self.capitals = Some(c);
+        return None;
+    }
+    pub fn set_punctuation(&mut self, p : Count) -> Option<FieldError> {
+        if p > 100 {
+            return Some(FieldError::NotAPercentage);
+        }
+        self.punctuation = Some(p);
+        return None;
+    }
+    pub fn set_brackets(&mut self, b : Count) -> Option<FieldError> {
+        if b > 100 {
+            return Some(FieldError::NotAPercentage);
+        }
+        self.brackets = Some(b);
+        return None;
+    }
+    pub fn set_numbers(&mut self, n : Count) -> Option<FieldError> {
+        if n > 100 {
+            return Some(FieldError::NotAPercentage);
+        }
+        self.numbers = Some(n);
+        return None;
+    }
+
+    pub fn get_top(&self) -> Option<Count> {
+        return self.top;
+    }
+    pub fn set_top(&mut self, t : Count) -> Option<FieldError> {
+        if t < 1 {
+            return Some(FieldError::ZeroNotAllowed)
+        }
+        self.top = Some(t);
+        return None;
+    }
+
+    pub fn get_ngram(&self) -> Count {
+        return self.ngram.unwrap_or(NGRAM);
+    }
+    pub fn set_ngram(&mut self, n : Count) -> Option<FieldError> {
+        if n < 1 {
+            return Some(FieldError::ZeroNotAllowed)
+        }
+        self.ngram = Some(n);
+        return None;
+    }
+
+    pub fn get_lesson(&self) -> Option<Count> {
+        return self.lesson;
+    }
+    pub fn set_lesson(&mut self, l : Count) -> Option<FieldError> {
+        if l < 1 {
+            return Some(FieldError::ZeroNotAllowed)
         }
+        self.lesson = Some(l);
+        return None;
+    }
+
+    pub fn get_min_length(&self) -> Option<Count> {
+        return self.min_length;
+    }
+    pub fn set_min_length(&mut self, m : Count) {
+        self.min_length = Some(m);
+    }
+
+    pub fn get_max_length(&self) -> Option<Count> {
+        return self.max_length;
     }
-    pub fn set_rate(&mut self, f : u16) -> Option<FieldError> {
-        if f < 1 {
+    pub fn set_max_length(&mut self, m : Count) -> Option<FieldError> {
+        if m < 1 {
             return Some(FieldError::ZeroNotAllowed)
         }
-        self.lenght = Some(f);
+        self.max_length = Some(m);
         return None;
     }
 
+    pub fn get_only(&self) -> Option<String> {
+        return self.only.clone();
+    }
+    pub fn set_only(&mut self, o : &str) {
+        self.only = Some(o.to_string());
+    }
+
+    pub fn get_contains(&self) -> Option<String> {
+        return self.contains.clone();
+    }
+    pub fn set_contains(&mut self, c : &str) {
+        self.contains = Some(c.to_string());
+    }
+
+    pub fn get_pattern(&self) -> Option<String> {
+        return self.pattern.clone();
+    }
+    pub fn set_pattern(&mut self, p : &str) -> Option<FieldError> {
+        if regex::Regex::new(p).is_err() {
+            return Some(FieldError::InvalidPattern);
+        }
+        self.pattern = Some(p.to_string());
+        return None;
+    }
+
+    pub fn get_selection(&self) -> Selection {
+        return self.selection.unwrap_or(SELECTION);
+    }
+    pub fn set_selection(&mut self, s : &str) -> Option<FieldError> {
+        if let Some(selection) = Selection::new(s) {
+            self.selection = Some(selection);
+            return None;
+        }
+        return Some(FieldError::InvalidSelection);
+    }
+
+    pub fn get_seed(&self) -> Option<u64> {
+        return self.seed;
+    }
+    pub fn set_seed(&mut self, s : u64) {
+        self.seed = Some(s);
+    }
+
+    pub fn get_text(&self) -> Option<String> {
+        return self.text.clone();
+    }
+    pub fn set_text(&mut self, t : &str) {
+        self.text = Some(t.to_string());
+    }
+
     // TO DO: Set a nice ConfigTextBox
 
     pub fn get_theme(&self) -> Theme { 
@@ -262,7 +626,11 @@ impl Config {
 mod test {
     use std::time::Duration;
 
-    use crate::{config::{self, Theme}, path_to_file};
+    use crate::{
+        config::{self, TestKind, Theme}, 
+        keyboard::{HeatmapMetric, Layout},
+        path_to_file
+    };
 
     use super::Config;
 
@@ -277,10 +645,51 @@ mod test {
         assert!(config.get_max_time() == Duration::from_secs(config::MAX_TIME));
         assert!(config.get_lenght() == config::LENGHT);
         assert!(config.get_theme() == Theme::default());
-        assert!(config.get_rate() == config::RATE);
+        assert!(config.get_words().is_none());
+        assert!(config.get_layout() == config::LAYOUT);
+        assert!(config.get_heatmap() == config::HEATMAP);
 
     }
 
+    #[test]
+    fn words_test_kind() {
+        let mut config = Config::from_str("words = 50").unwrap();
+        assert!(config.get_test_kind() == TestKind::Words(50));
+
+        config.set_max_time(30);
+        assert!(config.get_test_kind() == TestKind::Words(50));
+
+        let config = Config::from_str("max_time = 30").unwrap();
+        assert!(config.get_test_kind() == TestKind::Time(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn heatmap_config() {
+        let mut config = Config::from_str("layout = \"dvorak\"\nheatmap = \"latency\"").unwrap();
+        assert!(config.get_layout() == Layout::Dvorak);
+        assert!(config.get_heatmap() == HeatmapMetric::Latency);
+
+        assert!(config.set_layout("colemak").is_none());
+        assert!(config.get_layout() == Layout::Colemak);
+        assert!(config.set_layout("qwertz").is_some());
+
+        assert!(Config::from_str("layout = \"qwertz\"").is_err());
+    }
+
+    #[test]
+    fn percentages_checked() {
+        assert!(Config::from_str("capitals = 100\nnumbers = 0").is_ok());
+        let error = Config::from_str("capitals = 500").unwrap_err();
+        assert!(error.to_string().starts_with("capitals: not a percentage"));
+        assert!(Config::from_str("brackets = 101").is_err());
+    }
+
+    #[test]
+    fn obsolete_rate() {
+        assert!(Config::from_str("rate = 1000").unwrap().has_rate());
+        assert!(!Config::from_str("").unwrap().has_rate());
+    }
+
     #[test]
     fn error_thrown() {
 
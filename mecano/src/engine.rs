@@ -1,7 +1,7 @@
 use std::{
     cmp::min,
-    io::{self, stdout, Write},
-    thread,
+    io::{self, Write},
+    path::Path,
     time::{Duration, Instant},
     option::Option,
 };
@@ -10,19 +10,30 @@ use crossterm::{
     cursor::{self, MoveDown, MoveTo, MoveToColumn}, 
     event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers}, 
     style::Print, 
-    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
-    execute, queue, 
+    terminal::{Clear, ClearType},
+    queue, 
 };
 
 use super::TermUnit;
 
 use crate::{
-    config::Config, 
-    mode::{SourceDictionary, SourceFile, WordSource}, 
+    backend::{Backend, CrosstermBackend},
+    cli::{flags::all_dictionaries, history_file_path, quotes_path},
+    config::{Config, TestKind, Theme}, 
+    history::{self, Record},
+    keyboard::Heatmap,
+    mode::transform::Transforms,
+    mode::{SourceAdaptive, SourceCode, SourceCommand, SourceDictionary, SourceFile, SourceFiltered, SourceLessons, SourceMix, SourcePseudo, SourceQuote, SourceReplay, SourceTransformed, SourceWikipedia, WordSource, TEXT_MODES, filter::WordFilter}, 
     punctuation::Punct,
     textbox::Text
 };
 
+// Modes the results menu switches between, the ones playable without
+// options of their own
+const MENU_MODES : [&str; 5] = ["dictionary", "adaptive", "pseudo", "quote", "lessons"];
+// Modes that take their words from a dictionary
+const DICTIONARY_MODES : [&str; 4] = ["dictionary", "adaptive", "pseudo", "lessons"];
+
 #[derive(Debug)]
 enum Engine {
     Ready,
@@ -56,16 +67,15 @@ impl BoxInfo {
     }
 }
 
-impl Default for BoxInfo {
-    fn default() -> Self {
-        let size = crossterm::terminal::size().unwrap();
-
-        BoxInfo {
+impl BoxInfo {
+    // Whole screen, when it's too narrow for the box
+    fn full(size : (TermUnit, TermUnit)) -> BoxInfo {
+        return BoxInfo {
             left_padding : 0,
             top_padding : 0,
             width : 0,
             size,
-        }
+        };
     }
 }
 
@@ -78,73 +88,73 @@ pub struct Mecano {
     lines_to_show : TermUnit,
     engine : Engine,
     punct : Option<Punct>,
+    mode : String,
+    file : String,
+    title : Option<String>,
+    transforms : Transforms,
+    seed : Option<u64>,
+    kind : TestKind,
+    heatmap : Heatmap,
+    theme : Theme,
+    // Kept to start new tests from the results
+    config : Config,
+    backend : Box<dyn Backend>,
 }
 
 impl Mecano {
 
     pub fn play(config : Config) -> io::Result<()> {
-        let fps = config.get_rate();
-        let frame_duration = Duration::from_secs_f64(1.0 / fps as f64);
-        let mut delta;
-        let mut chrono = Instant::now();
-
-        let mut engine = Mecano::new(config)?;
+        let mut engine = Mecano::new(config, Box::new(CrosstermBackend::new()))?;
         engine.draw()?;
+        let mut saved = Ok(());
 
-        while !engine.is_ended() {
-
-            while let Ok(true) = poll(Duration::ZERO) {
-                if !engine.is_running() && !engine.is_too_narrow() {
-                    engine.run();
+        // Each round is a test and its results, until the game is quit.
+        // The loop sleeps until a key comes or the clock shown changes
+        loop {
+            while !engine.is_ended() {
+                let event = match engine.until_clock_tick() {
+                    Some(timeout) if !poll(timeout)? => None,
+                    _ => Some(read()?),
+                };
+                let now = Instant::now();
+
+                if event.is_some() && !engine.is_running() && !engine.is_too_narrow() {
+                    engine.run(now);
                 }
-                let keep_going = engine.event_read()?;
-                if !keep_going {
-                    return Ok(());
+                if engine.is_running() {
+                    engine.update_time(now)?;
+                }
+                if let Some(event) = event {
+                    if !engine.handle_event(event, now)? {
+                        return saved;
+                    }
+                    engine.backend.flush()?;
                 }
             }
 
-            if engine.is_running() {
-                engine.update_time(frame_duration)?;
-            }
-
-            delta = frame_duration - min(frame_duration, chrono.elapsed());
-            thread::sleep(delta);
-            chrono = Instant::now();
-        }
-
-        engine.draw()?;
-        loop {
-            while let Ok(true) = poll(Duration::ZERO) {
-                if !engine.event_read()? {
-                    return Ok(());
+            saved = saved.and(engine.save_punct());
+            engine.draw()?;
+            while engine.is_ended() {
+                if !engine.handle_event(read()?, Instant::now())? {
+                    return saved;
                 }
             }
-
-            delta = frame_duration - min(frame_duration, chrono.elapsed());
-            thread::sleep(delta);
-            chrono = Instant::now();
         }
     }
 
-    fn new(config : Config) -> io::Result<Mecano> {
-        let words_source = Mecano::word_source(&config);
-
-        crossterm::terminal::enable_raw_mode()?;
-        execute!(stdout(), EnterAlternateScreen)?;
+    fn new(config : Config, mut backend : Box<dyn Backend>) -> io::Result<Mecano> {
+        let words_source = Mecano::word_source(&config)?;
+        let words_source_title = words_source.title();
+        let kind = words_source.passage_len()
+            .map_or(config.get_test_kind(), TestKind::Passage);
 
-        let box_info = BoxInfo::centered(
-            config.get_width(), 
-            crossterm::terminal::size().unwrap_or((0, 0))
-        ).unwrap_or_default();
+        backend.enter()?;
+        let size = backend.size().unwrap_or((0, 0));
+        let box_info = BoxInfo::centered(config.get_width(), size)
+            .unwrap_or(BoxInfo::full(size));
 
-        let buffer = Text::new(
-            words_source,
-            config.get_theme().clone(),
-            config.get_max_time(),
-            (std::cmp::min::<TermUnit>( box_info.size.0 / 2 - 2,
-                config.get_lenght()),
-                box_info.width)
-        );
+        let buffer = Mecano::new_text(&config, words_source, kind, &box_info);
+        let lines_to_show = Mecano::lines_for(&config, kind, &buffer, &box_info);
 
         let state : Mecano = Mecano {
             typed_word : String::new(),
@@ -153,15 +163,118 @@ impl Mecano {
             box_info, 
             engine : Engine::Ready,
             width : config.get_width(),
-            lines_to_show : config.get_lenght(),
+            lines_to_show,
             punct : None,
+            mode : config.get_mode(),
+            file : Mecano::source_name(&config),
+            title : words_source_title,
+            transforms : config.get_transforms(),
+            seed : config.get_seed(),
+            kind,
+            heatmap : Heatmap::new(config.get_layout(), 
+                config.get_heatmap(), config.get_theme()),
+            theme : config.get_theme(),
+            config,
+            backend,
         };
 
         return Ok(state);
     }
 
+    fn new_text(config : &Config, words_source : Box<dyn WordSource>,
+        kind : TestKind, box_info : &BoxInfo) -> Text {
+        return Text::new(
+            words_source,
+            config.get_theme().clone(),
+            kind,
+            (std::cmp::min::<TermUnit>( box_info.size.0 / 2 - 2,
+                config.get_lenght()),
+                box_info.width)
+        );
+    }
+
+    // The whole passage is shown at once
+    fn lines_for(config : &Config, kind : TestKind, text : &Text, box_info : &BoxInfo) -> TermUnit {
+        let mut lines_to_show = config.get_lenght();
+        if let TestKind::Passage(_) = kind {
+            lines_to_show = std::cmp::max(lines_to_show, text.n_lines(box_info.width));
+        }
+        return lines_to_show;
+    }
+
+    // Starts another test without leaving the screen. A retry plays the
+    // words of the last test again
+    fn restart(&mut self, retry : bool) -> io::Result<()> {
+        let mut words_source = Mecano::word_source(&self.config)?;
+        if retry {
+            let passage_len = match self.kind {
+                TestKind::Passage(n_words) => Some(n_words),
+                _ => None,
+            };
+            words_source = Box::new(SourceReplay::new(
+                self.textbox.get_yielded(), self.title.clone(), passage_len, words_source));
+        }
+
+        let words_source_title = words_source.title();
+        self.kind = words_source.passage_len()
+            .map_or(self.config.get_test_kind(), TestKind::Passage);
+        self.textbox = Mecano::new_text(&self.config, words_source, self.kind, &self.box_info);
+        self.lines_to_show = Mecano::lines_for(&self.config, self.kind, &self.textbox, &self.box_info);
+
+        self.typed_word.clear();
+        self.input_offset = self.box_info.left_padding;
+        self.engine = Engine::Ready;
+        self.punct = None;
+        self.mode = self.config.get_mode();
+        self.file = Mecano::source_name(&self.config);
+        self.title = words_source_title;
+        self.transforms = self.config.get_transforms();
+        self.seed = self.config.get_seed();
+
+        queue!(self.backend, cursor::Show)?;
+        return self.draw();
+    }
+
+    // Results menu keys, switching mode or dictionary starts a new test
+    // Only on the results, so a stray key doesn't throw a test away
+    fn menu_key_event(&mut self, key : KeyEvent) -> io::Result<()> {
+        match key.code {
+            KeyCode::Tab => return self.restart(false),
+
+            KeyCode::BackTab => return self.restart(true),
+
+            KeyCode::Char('m') => {
+                let mode = self.config.get_mode();
+                let next = MENU_MODES.iter()
+                    .position(|m| *m == mode)
+                    .map_or(0, |n| (n + 1) % MENU_MODES.len());
+                self.config.set_mode(MENU_MODES[next]);
+                return self.restart(false);
+            }
 
-    fn run(&mut self) { self.engine = Engine::Run }
+            KeyCode::Char('d') => {
+                let dictionaries = all_dictionaries();
+                if dictionaries.is_empty() { return Ok(()); }
+                let file = Mecano::source_name(&self.config);
+                let next = dictionaries.iter()
+                    .position(|d| *d == file)
+                    .map_or(0, |n| (n + 1) % dictionaries.len());
+                self.config.set_file(&dictionaries[next]);
+                if !DICTIONARY_MODES.contains(&self.config.get_mode().as_str()) {
+                    self.config.set_mode("dictionary");
+                }
+                return self.restart(false);
+            }
+
+            _ => return Ok(()),
+        }
+    }
+
+
+    fn run(&mut self, now : Instant) {
+        self.textbox.resume(now);
+        self.engine = Engine::Run;
+    }
 
     fn is_running(&mut self) -> bool {
         return match self.engine { 
@@ -170,7 +283,18 @@ impl Mecano {
             } 
     }
 
-    fn stop(&mut self) { self.engine = Engine::Stop }
+    fn stop(&mut self, now : Instant) {
+        self.textbox.pause(now);
+        self.engine = Engine::Stop;
+    }
+
+    // Only a running clock needs waking up for
+    fn until_clock_tick(&self) -> Option<Duration> {
+        return match self.engine {
+            Engine::Run => Some(self.textbox.until_clock_tick()),
+            _ => None,
+        }
+    }
 
     fn is_stopped(&self) -> bool {
         return match self.engine { 
@@ -204,8 +328,8 @@ impl Mecano {
             } 
     }
 
-    fn update_time(&mut self, elapsed : Duration) -> io::Result<()> {
-        let keep_going = self.textbox.update_time(elapsed);
+    fn update_time(&mut self, now : Instant) -> io::Result<()> {
+        let keep_going = self.textbox.update_time(now);
         if !keep_going {
             self.end();
         }
@@ -220,8 +344,8 @@ impl Mecano {
 
     fn draw(&mut self) -> io::Result<()> {
 
-        queue!(stdout(), Clear(ClearType::All))?;
-        let real_size = crossterm::terminal::size().unwrap();
+        queue!(self.backend, Clear(ClearType::All))?;
+        let real_size = self.backend.size()?;
 
         if let Ok(box_info) = BoxInfo::centered(self.width, real_size) {
             self.box_info = box_info;
@@ -253,13 +377,70 @@ impl Mecano {
         return Ok(());
     }
 
-    fn word_source(config : &Config) -> Box<dyn WordSource> {
-        return match config.get_mode().as_str() {
-            "file" => Box::new(SourceFile::from_config(&config)),
-            "dictionary" => Box::new(SourceDictionary::from_config(&config)),
+    fn word_source(config : &Config) -> io::Result<Box<dyn WordSource>> {
+        let mut source : Box<dyn WordSource> = match config.get_mode().as_str() {
+            "file" => Box::new(SourceFile::from_config(&config)?),
+            "dictionary" if !config.get_mix().is_empty() => Box::new(SourceMix::from_config(&config)?),
+            "dictionary" => Box::new(SourceDictionary::from_config(&config)?),
+            "adaptive" => Box::new(SourceAdaptive::from_config(&config)?),
+            "command" => Box::new(SourceCommand::from_config(&config)?),
+            "wikipedia" => Box::new(SourceWikipedia::from_config(&config)?),
+            "code" => Box::new(SourceCode::from_config(&config)?),
+            "quote" => Box::new(SourceQuote::from_config(&config)?),
+            "lessons" => Box::new(SourceLessons::from_config(&config)?),
+            "pseudo" => Box::new(SourcePseudo::from_config(&config)?),
             _ => panic!()
 
+        };
+
+        let filter = WordFilter::from_config(config)?;
+        if !filter.is_empty() {
+            match config.get_mode().as_str() {
+                // Word lists filter when they load
+                "dictionary" | "adaptive" | "pseudo" => (),
+                // Passages and code are kept whole
+                mode @ ("quote" | "code") => {
+                    let error_msg = format!("{mode} mode is played whole, word filters don't apply to it");
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+                },
+                _ => source = Box::new(SourceFiltered::new(source, filter)?),
+            }
         }
+
+        let transforms = config.get_transforms();
+        if transforms.is_empty() {
+            return Ok(source);
+        }
+        return Ok(Box::new(SourceTransformed::new(source, transforms, config.get_seed())));
+    }
+
+    // Name of the text source saved in the history
+    fn source_name(config : &Config) -> String {
+        if config.get_mode() == "command" {
+            return config.get_command().unwrap_or_default();
+        }
+        let mix = config.get_mix();
+        if config.get_mode() == "dictionary" && !mix.is_empty() {
+            return mix.iter()
+                .map(|(path, ratio)| {
+                    let name = file_name(path);
+                    format!("{name}:{ratio}")
+                })
+                .collect::<Vec<String>>()
+                .join(",");
+        }
+        // Quotes and articles keep their title apart
+        if config.get_mode() == "quote" {
+            return file_name(&quotes_path());
+        }
+        if config.get_mode() == "wikipedia" {
+            return config.get_wikipedia().map_or(String::new(), |dump| file_name(&dump));
+        }
+        let takes_text = TEXT_MODES.contains(&config.get_mode().as_str());
+        if takes_text && config.get_text().is_some() {
+            return String::from("stdin");
+        }
+        return file_name(&config.get_file());
     }
 
     // TO DO : Add controls information for size
@@ -275,15 +456,31 @@ impl Mecano {
         self.print_text_box()?;
         self.go_to_input_beginning()?;
         let word = &self.typed_word;
-        write!(stdout(), "{word}")?;
-        stdout().flush()?;
+        write!(self.backend, "{word}")?;
+        self.backend.flush()?;
 
         return Ok(());
     }
 
+    // Computes the final punct and appends it to the history. A failure
+    // saving what the source learnt doesn't lose the test
+    fn save_punct(&mut self) -> io::Result<()> {
+        let mut punct = self.textbox.get_punct();
+        punct.set_heatmap(self.heatmap);
+        punct.set_theme(self.theme);
+        punct.set_title(self.title.clone());
+        punct.set_transforms(self.transforms);
+        punct.set_seed(self.seed);
+        let record = Record::new(&punct, &self.mode, &self.file, self.kind);
+        let finished = self.textbox.finish(&punct);
+        self.punct = Some(punct);
+        let appended = history::append(&history_file_path(), &record);
+        return appended.and(finished);
+    }
+
     fn draw_punct(&mut self) -> io::Result<()> {
-        queue!(stdout(), Clear(ClearType::All))?;
-        let size = crossterm::terminal::size()?;
+        queue!(self.backend, Clear(ClearType::All))?;
+        let size = self.backend.size()?;
         self.draw_box(self.outter_box_pos(), size)?;
         if let None = self.punct {
             self.punct = Some(self.textbox
@@ -291,30 +488,43 @@ impl Mecano {
         }
 
         // This if is always true
+        // The last line is left for the menu
         if let Some(p) = &mut self.punct {
             let mut size = size;
             size.0 -= min(size.0, 2);
-            size.1 -= min(size.1, 2);
+            size.1 -= min(size.1, 3);
             p.set_pos((1, 1));
             p.set_size(size);
         }
 
         let punct = self.punct.as_ref().unwrap();
-        write!(stdout(), "{punct}")?;
-        queue!(stdout(), cursor::Hide)?;
-        stdout().flush()?;
+        write!(self.backend, "{punct}")?;
+        self.print_menu(size)?;
+        queue!(self.backend, cursor::Hide)?;
+        self.backend.flush()?;
 
         return Ok(());
     }
 
+    fn print_menu(&mut self, size : (TermUnit, TermUnit)) -> io::Result<()> {
+        let mode = self.config.get_mode();
+        let file = Mecano::source_name(&self.config);
+        let menu = format!(
+            "TAB new test   SHIFT+TAB retry   M mode: {mode}   D dictionary: {file}   ESC quit");
+        let menu : String = menu.chars().take(size.0.saturating_sub(4) as usize).collect();
+        let x = (size.0 - min(size.0, menu.chars().count() as TermUnit)) / 2;
+        queue!(self.backend, MoveTo(x, size.1.saturating_sub(2)), Print(menu))?;
+        return Ok(());
+    }
+
 
     fn draw_too_narrow(&mut self) -> io::Result<()> {
         self.go_to_top_left()?;
-        queue!(stdout(),
+        queue!(self.backend,
             Clear(ClearType::All),
             Print("\rTOO NARROW. RESIZE.")
         )?;
-        stdout().flush()?;
+        self.backend.flush()?;
         return Ok(());
     }
 
@@ -327,30 +537,30 @@ impl Mecano {
         let lenght = box_size.1;
 
 
-        queue!(stdout(), MoveTo(x, y))?;
+        queue!(self.backend, MoveTo(x, y))?;
 
         let line_width = &"━".repeat(width as usize - 2);
         let top_border = format!("┏{line_width}┓");
-        write!(stdout(), "{top_border}")?;
+        write!(self.backend, "{top_border}")?;
 
-        queue!(stdout(), MoveTo(x, y + 1))?;
+        queue!(self.backend, MoveTo(x, y + 1))?;
 
         let blank_width = " ".repeat(width as usize - 2);
         for _row in 2 .. lenght {
-            queue!(stdout(), MoveToColumn(x))?;
-            write!(stdout(), "┃{blank_width}┃")?;
-            queue!(stdout(), MoveDown(1))?;
+            queue!(self.backend, MoveToColumn(x))?;
+            write!(self.backend, "┃{blank_width}┃")?;
+            queue!(self.backend, MoveDown(1))?;
         }
 
         let bottom_border = format!("┗{line_width}┛");
 
-        queue!(stdout(), MoveToColumn(x))?;
-        write!(stdout(), "{bottom_border}")?;
+        queue!(self.backend, MoveToColumn(x))?;
+        write!(self.backend, "{bottom_border}")?;
         return Ok(());
     }
 
     // REFACTOR
-    fn type_key_event(&mut self, key : KeyEvent) -> io::Result<bool> {
+    fn type_key_event(&mut self, key : KeyEvent, now : Instant) -> io::Result<bool> {
         match (key.modifiers, key.code) {
             (_, KeyCode::Esc) => return Ok(false),
             (KeyModifiers::CONTROL, KeyCode::Char(c)) => {
@@ -359,16 +569,29 @@ impl Mecano {
             (_, _) => (),
         }
 
-        if self.is_ended() { return Ok(true); }
+        if self.is_ended() {
+            self.menu_key_event(key)?;
+            return Ok(true);
+        }
         if self.is_too_narrow() { return Ok(true) }
         if key.modifiers != KeyModifiers::SHIFT &&
         key.modifiers != KeyModifiers::empty() { return Ok(true) }
 
         match key.code {
-            KeyCode::Char(c) => {
+            KeyCode::Char(_) | KeyCode::Enter => {
+                let c = if let KeyCode::Char(c) = key.code { c } else { '\n' };
+                if c == '\n' && self.textbox.separator() != '\n' {
+                    return Ok(true);
+                }
+                let ends_word = c == self.textbox.separator();
+
                 self.go_to_text()?;
-                self.textbox.type_char(c)?;
-                if c.is_whitespace() {
+                self.textbox.type_char(c, now, &mut self.backend)?;
+                if self.textbox.is_finished() {
+                    self.end();
+                    return Ok(true);
+                }
+                if ends_word {
                     self.typed_word.clear();
                     self.input_offset = self.box_info.left_padding;
                 } else {
@@ -381,31 +604,31 @@ impl Mecano {
 
             KeyCode::Backspace => {
                 self.go_to_text()?;
-                self.textbox.backspace()?;
+                self.textbox.backspace(&mut self.backend)?;
                 self.typed_word.pop().map(|_| self.input_offset -= 1);
                 self.print_input()?;
             },
 
             KeyCode::Right => {
-                self.stop();
+                self.stop(now);
                 self.width += 4;
                 self.draw()?;
             },
 
             KeyCode::Left => {
-                self.stop();
+                self.stop(now);
                 self.width -= std::cmp::min(self.width, 4);
                 self.draw()?;
             },
 
             KeyCode::Down => {
-                self.stop();
+                self.stop(now);
                 self.lines_to_show += 1;
                 self.draw()?;
             },
 
             KeyCode::Up => {
-                self.stop();
+                self.stop(now);
                 self.lines_to_show -= std::cmp::min(self.lines_to_show, 1);
                 self.draw()?;
             },
@@ -417,20 +640,19 @@ impl Mecano {
 
     fn print_text_box(&mut self) -> io::Result<()> {
         self.go_to_text()?;
-        let textbox = &self.textbox;
-        write!(stdout(), "{textbox}")?;
+        self.textbox.print(&mut self.backend)?;
         self.go_to_input()?;
         return Ok(());
     }
 
     fn print_time(&mut self) -> io::Result<()> {
         if self.is_too_narrow() { return Ok(()) }
-        let secs = self.textbox.get_remaining_time().as_secs() % 60;
-        let mins = self.textbox.get_remaining_time().as_secs() / 60;
+        let secs = self.textbox.get_clock().as_secs() % 60;
+        let mins = self.textbox.get_clock().as_secs() / 60;
         self.go_to_time()?;
-        write!(stdout(), "{mins:0>2}:{secs:0>2}")?; 
+        write!(self.backend, "{mins:0>2}:{secs:0>2}")?; 
         self.go_to_input()?;
-        stdout().flush()?;
+        self.backend.flush()?;
         return Ok(());
     }
 
@@ -441,10 +663,10 @@ impl Mecano {
         let max = std::cmp::min(self.typed_word.len(), self.width as usize);
         if max > 0 {
             let input_but_last : String= self.typed_word.chars().take(max - 1).collect();
-            write!(stdout(), "{input_but_last}")?;
+            write!(self.backend, "{input_but_last}")?;
         }
         if let Some(c) = self.typed_word.chars().last() {
-            write!(stdout(), "{c}")?;
+            write!(self.backend, "{c}")?;
         }
         self.go_to_input()?;
         return Ok(());
@@ -452,13 +674,13 @@ impl Mecano {
 
     fn print_blank(&mut self) -> io::Result<()> {
         let empty_space = " ".repeat(self.box_info.width as usize);
-        write!(stdout(), "{empty_space}")?;
+        write!(self.backend, "{empty_space}")?;
         return Ok(());
     }
 
     fn go_to_input(&mut self) -> io::Result<()> {
         let lines_to_show = self.lines_to_show();
-        queue!(stdout(),
+        queue!(self.backend,
             MoveTo(self.input_offset
                 , self.box_info.top_padding + lines_to_show + 1))?;
         return Ok(());
@@ -466,26 +688,26 @@ impl Mecano {
 
     fn go_to_input_beginning(&mut self) -> io::Result<()> {
         let lines_to_show = self.lines_to_show();
-        queue!(stdout(),
+        queue!(self.backend,
             MoveTo( self.box_info.left_padding, 
                     self.box_info.top_padding + lines_to_show + 1))?;
         return Ok(());
     }
 
     fn go_to_text(&mut self) -> io::Result<()> {
-        queue!(stdout(),
+        queue!(self.backend,
             MoveTo(self.box_info.left_padding,
                 self.box_info.top_padding))?;
         return Ok(());
     }
 
     fn go_to_time(&mut self) -> io::Result<()> {
-        return queue!(stdout(), 
+        return queue!(self.backend, 
             MoveTo(self.box_info.left_padding, self.box_info.top_padding - 2));
     }
 
     fn go_to_top_left(&mut self) -> io::Result<()> {
-        queue!(stdout(),
+        queue!(self.backend,
             MoveTo(0, 0))?;
         return Ok(());
     }
@@ -512,33 +734,32 @@ impl Mecano {
             self.lines_to_show);
     }
 
-    fn event_read(&mut self) -> io::Result<bool> {
-        if let Ok(event) = read() {
-            match event {
-                Event::Key(k) => {
-                    if !self.is_too_narrow() {
-                        return self.type_key_event(k);
-                    } 
-                },
-                Event::Resize(_, _) => {
-                    if !self.is_ended() {
-                        self.stop();
-                    }
-                    self.draw()?;
+    // Events are stamped with when they were read
+    fn handle_event(&mut self, event : Event, now : Instant) -> io::Result<bool> {
+        match event {
+            Event::Key(k) => {
+                if !self.is_too_narrow() {
+                    return self.type_key_event(k, now);
+                } 
+            },
+            Event::Resize(_, _) => {
+                if !self.is_ended() {
+                    self.stop(now);
                 }
-                Event::FocusGained => {
-                    if !self.is_ended() {
-                        self.run();
-                    }
+                self.draw()?;
+            }
+            Event::FocusGained => {
+                if !self.is_ended() {
+                    self.run(now);
                 }
-                Event::FocusLost => {
-                    if !self.is_ended() {
-                        self.stop();
-                    }
+            }
+            Event::FocusLost => {
+                if !self.is_ended() {
+                    self.stop(now);
                 }
-
-                _ => (),
             }
+
+            _ => (),
         }
         return Ok(true);
     }
@@ -546,29 +767,33 @@ impl Mecano {
 
 impl Drop for Mecano {
     fn drop (&mut self) {
-        let _ = crossterm::terminal::disable_raw_mode();
-        let _ = execute!(stdout(), LeaveAlternateScreen, cursor::Show);
+        let _ = self.backend.leave();
     }
 }
 
+fn file_name(path : &Path) -> String {
+    return path.file_name().map_or(String::new(), |f| f.to_string_lossy().to_string());
+}
+
 #[cfg(test)]
 mod test {
-    use std::{io::{stdout, Write}, thread, time::{Duration, Instant}};
+    use std::time::{Duration, Instant};
 
-    use crossterm::event::{poll, KeyCode, KeyEvent, KeyModifiers};
+    use crossterm::{event::{KeyCode, KeyEvent, KeyModifiers}, style::Attribute};
 
-    use crate::{config::Config, engine::Mecano, path_to_file};
+    use crate::{backend::VirtualScreen, config::Config, engine::Mecano, path_to_file};
 
 
     #[test]
     // REFACTOR
     fn keys_all_right() {
         let config = Config::default_test();
-        let mut state = Mecano::new(config).unwrap();
+        let mut state = Mecano::new(config, Box::new(VirtualScreen::new((120, 30)))).unwrap();
 
         let _ = state.draw();
-        let _ = stdout().flush();
         let frame_duration = Duration::from_millis(100);
+        let mut now = Instant::now();
+        state.run(now);
 
         let find_path_to_file = path_to_file("100_english").unwrap();
         let contents = std::fs::read_to_string(find_path_to_file)
@@ -581,13 +806,8 @@ mod test {
         let mut iter = text.chars();
         let mut spaces = 0;
 
-        state.run();
         while !state.is_ended() {
 
-            if let Ok(true) = poll(Duration::ZERO) {
-                break;
-            }
-
             let c = iter.next().unwrap_or_else(|| {
                 iter = text.chars();
                 return iter.next().unwrap();
@@ -601,9 +821,10 @@ mod test {
             KeyEvent::new(KeyCode::Char(c)
                 , KeyModifiers::empty());
 
-            let _ = state.type_key_event(keyevent);
+            let _ = state.type_key_event(keyevent, now);
 
-            let _ = state.update_time(frame_duration);
+            now += frame_duration;
+            let _ = state.update_time(now);
         }
 
         let punct = state.textbox.get_punct();
@@ -625,8 +846,10 @@ mod test {
     fn too_narrow() {
 
         let config = Config::default_test();
-        let mut state = Mecano::new(config).unwrap();
+        let screen = VirtualScreen::new((120, 30));
+        let mut state = Mecano::new(config, Box::new(screen.clone())).unwrap();
         let _ = state.draw();
+        assert!(screen.find("TOO NARROW").is_none());
 
 
         let mut make_widder = vec![ 
@@ -638,15 +861,11 @@ mod test {
             50];
 
         let frame_duration = Duration::from_millis(100);
+        let mut now = Instant::now();
+        state.run(now);
 
         while !state.is_ended() {
 
-            thread::sleep(Duration::from_millis(1));
-
-            if let Ok(true) = poll(Duration::ZERO) {
-                break;
-            }
-
             let keyevent;
 
             if let Some(k) = make_widder.pop() {
@@ -657,17 +876,80 @@ mod test {
                 break;
             }
 
-            let _ = state.type_key_event(keyevent);
+            let _ = state.type_key_event(keyevent, now);
 
-            let _ = state.update_time(frame_duration);
+            now += frame_duration;
+            let _ = state.update_time(now);
         }
+        assert_eq!(screen.line(0).trim_end(), "TOO NARROW. RESIZE.");
+    }
+
+    #[test]
+    fn draws_on_virtual_screen() {
+        let config = Config::default_test();
+        let theme = config.get_theme();
+        let screen = VirtualScreen::new((120, 30));
+        let mut state = Mecano::new(config, Box::new(screen.clone())).unwrap();
+        let _ = state.draw();
+        assert!(screen.is_entered());
+        assert!(screen.find("01:00").is_some());
+
+        let first_word = state.textbox.get_yielded()[0].clone();
+        let (x, y) = screen.find(&first_word).unwrap();
+        assert_eq!(screen.cell(x, y).fg, theme.get_selected());
+        assert!(screen.cell(x, y).attributes.has(Attribute::Underlined));
+
+        // A right char and a wrong one
+        let now = Instant::now();
+        state.run(now);
+        let right = first_word.chars().next().unwrap();
+        let _ = state.type_key_event(KeyEvent::new(KeyCode::Char(right), KeyModifiers::empty()), now);
+        let _ = state.type_key_event(KeyEvent::new(KeyCode::Char('#'), KeyModifiers::empty()), now);
+        assert_eq!(screen.cell(x, y).fg, theme.get_right());
+        assert_eq!(screen.cell(x + 1, y).fg, theme.get_wrong());
+        assert!(screen.find(&format!("{right}#")).is_some_and(|(_, input_y)| input_y > y));
+
+        let _ = state.update_time(now + Duration::from_secs(3));
+        assert!(screen.find("00:57").is_some());
+
+        state.end();
+        let _ = state.draw();
+        assert!(screen.find("WPM").is_some());
+        assert!(screen.find("TAB new test").is_some());
+        assert!(!screen.is_cursor_shown());
+
+        drop(state);
+        assert!(!screen.is_entered());
+    }
+
+    #[test]
+    fn restart_and_retry() {
+        let config = Config::default_test();
+        let mut state = Mecano::new(config, Box::new(VirtualScreen::new((120, 30)))).unwrap();
+        let _ = state.draw();
+
+        let played = state.textbox.get_yielded().to_vec();
+        let _ = state.type_key_event(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::empty()), Instant::now());
+        // Ignored while typing
+        let _ = state.type_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::empty()), Instant::now());
+        assert_eq!(state.typed_word, "x");
+
+        state.end();
+        let _ = state.type_key_event(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT), Instant::now());
+        assert!(state.is_ready());
+        assert!(state.typed_word.is_empty());
+        assert_eq!(&state.textbox.get_yielded()[..played.len()], &played[..]);
+
+        state.end();
+        let _ = state.type_key_event(KeyEvent::new(KeyCode::Tab, KeyModifiers::empty()), Instant::now());
+        assert!(state.is_ready());
     }
 
     #[test]
     #[ignore]
     fn infinite() {
         let config = Config::max_time();
-        let mut state = Mecano::new(config).unwrap();
+        let mut state = Mecano::new(config, Box::new(VirtualScreen::new((120, 30)))).unwrap();
         let _ = state.draw();
 
         let find_path_to_file = path_to_file("100_english").unwrap();
@@ -682,21 +964,20 @@ mod test {
         let mut iter = text.chars().cycle();
 
         let frame_duration = Duration::from_millis(100);
+        let mut now = Instant::now();
+        state.run(now);
 
         while !state.is_ended() {
 
-            if let Ok(true) = poll(Duration::ZERO) {
-                break;
-            }
-
             let c = iter.next().unwrap_or('\0');
 
             let keyevent = 
                 KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
 
-            let _ = state.type_key_event(keyevent);
+            let _ = state.type_key_event(keyevent, now);
 
-            let _ = state.update_time(frame_duration);
+            now += frame_duration;
+            let _ = state.update_time(now);
         }
     }
 
@@ -704,7 +985,7 @@ mod test {
     fn game_100000_secs() {
         let mut config = Config::max_time();
         config.set_max_time(100000);
-        let mut state = Mecano::new(config).unwrap();
+        let mut state = Mecano::new(config, Box::new(VirtualScreen::new((120, 30)))).unwrap();
         let _ = state.draw();
 
         let find_path_to_file = path_to_file("100_english").unwrap();
@@ -719,23 +1000,42 @@ mod test {
         let mut iter = text.chars().cycle();
 
         let frame_duration = Duration::from_millis(100);
+        let mut now = Instant::now();
+        state.run(now);
 
         while !state.is_ended()  {
 
-            if let Ok(true) = poll(Duration::ZERO) {
-                break;
-            }
-
             let c = iter.next().unwrap_or('\0');
 
             let keyevent = 
             KeyEvent::new(KeyCode::Char(c), KeyModifiers::empty());
 
-            let _ = state.type_key_event(keyevent);
+            let _ = state.type_key_event(keyevent, now);
 
-            let _ = state.update_time(frame_duration);
+            now += frame_duration;
+            let _ = state.update_time(now);
         }
 
         let _ = state.draw();
     }
+
+    #[test]
+    fn filters_every_source() {
+        let mut config = Config::default_test();
+        config.set_min_length(4);
+        config.set_mode("dictionary");
+        let mut source = Mecano::word_source(&config).unwrap();
+        for _ in 0..100 {
+            assert!(source.yield_word().chars().count() >= 4);
+        }
+
+        config.set_mode("file");
+        let mut source = Mecano::word_source(&config).unwrap();
+        for _ in 0..100 {
+            assert!(source.yield_word().chars().count() >= 4);
+        }
+
+        config.set_mode("quote");
+        assert!(Mecano::word_source(&config).is_err());
+    }
 }
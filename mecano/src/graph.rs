@@ -0,0 +1,164 @@
+use crossterm::style::{Color, SetForegroundColor};
+
+use crate::{config::Theme, Count, TermUnit};
+
+const SECS_PER_MIN : f64 = 60.0;
+const CHARS_PER_WORD : f64 = 5.0;
+const WPM_POINT : char = '•';
+const RAW_POINT : char = '·';
+const ERROR_MARK : char = 'x';
+const LABEL_WIDTH : usize = 5;
+
+// Cumulative values at the end of a typed word
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub secs : f64,
+    pub right : Count,
+    pub wrong : Count,
+}
+
+// WPM, raw and errors of the words falling in a graph column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Column {
+    pub wpm : f64,
+    pub raw : f64,
+    pub errors : Count,
+}
+
+// Groups the samples in `width` columns. With less samples than columns
+// each sample is stretched over several columns
+pub fn columns(samples : &[Sample], width : usize) -> Vec<Column> {
+    let mut columns = Vec::new();
+    if samples.is_empty() {
+        return columns;
+    }
+
+    let origin = Sample { secs : 0.0, right : 0, wrong : 0 };
+    let n = samples.len();
+    for i in 0..width {
+        let start = i * n / width;
+        let end = ((i + 1) * n / width).max(start + 1);
+        let before = if start == 0 { origin } else { samples[start - 1] };
+        let last = samples[end - 1];
+
+        let secs = last.secs - before.secs;
+        let right = last.right - before.right;
+        let wrong = last.wrong - before.wrong;
+
+        let mut wpm = 0.0;
+        let mut raw = 0.0;
+        if secs > 0.0 {
+            wpm = right as f64 / secs * SECS_PER_MIN / CHARS_PER_WORD;
+            raw = (right + wrong) as f64 / secs * SECS_PER_MIN / CHARS_PER_WORD;
+        }
+        columns.push(Column { wpm, raw, errors : wrong });
+    }
+    return columns;
+}
+
+pub struct Graph {
+    width : TermUnit,
+    height : TermUnit,
+    theme : Theme,
+}
+
+impl Graph {
+    // Size including the labels and the bottom axis
+    pub fn new(size : (TermUnit, TermUnit), theme : Theme) -> Graph {
+        return Graph {
+            width : size.0,
+            height : size.1,
+            theme,
+        }
+    }
+
+    pub fn lines(&self, samples : &[Sample]) -> Vec<String> {
+        let plot_width = (self.width as usize).saturating_sub(LABEL_WIDTH + 1);
+        let plot_height = (self.height as usize).saturating_sub(1);
+        if plot_width == 0 || plot_height == 0 {
+            return Vec::new();
+        }
+
+        let columns = columns(samples, plot_width);
+        let max = columns.iter()
+            .map(|c| c.raw.max(c.wpm))
+            .fold(0.0, f64::max)
+            .max(1.0)
+            .ceil();
+        let row_of = |value : f64| -> usize {
+            let row = (value / max * (plot_height - 1) as f64).round() as usize;
+            return plot_height - 1 - row.min(plot_height - 1);
+        };
+
+        let mut grid = vec![vec![(' ', Color::Reset); plot_width]; plot_height];
+        for (x, column) in columns.iter().enumerate() {
+            grid[row_of(column.raw)][x] = (RAW_POINT, self.theme.get_selected());
+            grid[row_of(column.wpm)][x] = (WPM_POINT, self.theme.get_right());
+        }
+
+        let mut lines = Vec::new();
+        for (y, row) in grid.iter().enumerate() {
+            let label = match y {
+                0 => format!("{max:>w$}", w = LABEL_WIDTH - 1),
+                y if y == plot_height - 1 => format!("{:>w$}", 0, w = LABEL_WIDTH - 1),
+                _ => " ".repeat(LABEL_WIDTH - 1),
+            };
+            let mut line = format!("{label}┤");
+            for (c, color) in row {
+                line.push_str(&format!("{}{c}", SetForegroundColor(*color)));
+            }
+            line.push_str(&format!("{}", SetForegroundColor(Color::Reset)));
+            lines.push(line);
+        }
+
+        // Errors are marked on the time axis
+        let mut axis = format!("{}└", " ".repeat(LABEL_WIDTH - 1));
+        for column in &columns {
+            if column.errors > 0 {
+                axis.push_str(&format!("{}{ERROR_MARK}{}",
+                    SetForegroundColor(self.theme.get_wrong()),
+                    SetForegroundColor(Color::Reset)));
+            } else {
+                axis.push('─');
+            }
+        }
+        lines.push(axis);
+
+        return lines;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{columns, Sample};
+
+    fn sample(secs : f64, right : u64, wrong : u64) -> Sample {
+        return Sample { secs, right, wrong };
+    }
+
+    #[test]
+    fn columns_group_and_stretch() {
+        let samples = vec![
+            sample(1.0, 5, 0),
+            sample(2.0, 10, 0),
+            sample(3.0, 10, 5),
+            sample(4.0, 15, 5),
+        ];
+
+        let grouped = columns(&samples, 2);
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].wpm, 60.0);
+        assert_eq!(grouped[0].errors, 0);
+        assert_eq!(grouped[1].wpm, 30.0);
+        assert_eq!(grouped[1].raw, 60.0);
+        assert_eq!(grouped[1].errors, 5);
+
+        let stretched = columns(&samples, 8);
+        assert_eq!(stretched.len(), 8);
+        assert_eq!(stretched[0], stretched[1]);
+        assert_eq!(stretched[4].wpm, 0.0);
+        assert_eq!(stretched[4].errors, 5);
+
+        assert!(columns(&[], 8).is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Results history.
+//!
+//! Every finished test is appended as one JSON object per line (JSON Lines)
+//! to `~/.config/mecano/history.jsonl`. Each line is a [`Record`]:
+//!
+//! ```json
+//! {"version":2,"timestamp":1760781600,"mode":"dictionary","file":"100_english","kind":"time","target":60,"duration":60.0,"wpm":72.4,"raw":75.1,"acc":0.964,"right":362,"wrong":13,"extra":2,"missed":1,"transforms":{"capitals":0,"punctuation":10,"brackets":0,"numbers":0}}
+//! ```
+//!
+//! - `version`: format version, currently `2`. Bumped on incompatible changes.
+//!   Version `1` kept the title of quotes, articles and lessons in `file`.
+//! - `timestamp`: end of the test, in seconds since the Unix epoch.
+//! - `mode`, `file`: play mode and file name of the dictionary, text,
+//!   quotes or dump, or the command in command mode.
+//! - `title`: quote author, article or lesson played, only for the modes
+//!   that pick one.
+//! - `kind`, `target`: `"time"` with the seconds of the test, `"words"` with
+//!   the number of words of the test or `"passage"` with the number of words
+//!   of the passage typed in quote mode.
+//! - `duration`: real elapsed seconds.
+//! - `wpm`, `raw`: words per minute, counting right chars and all typed chars.
+//! - `acc`: accuracy between 0 and 1.
+//! - `right`, `wrong`, `extra`, `missed`: char counts.
+//! - `transforms`: percentage of words capitalised, punctuated, wrapped in
+//!   brackets or quotes and preceded by a number. All 0 when missing.
+//! - `seed`: seed of the words, only for `--seed` and `--daily` sessions.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::TestKind, mode::transform::Transforms, punctuation::Punct, Count};
+
+pub const VERSION : u32 = 2;
+// Modes whose title was saved in `file` before version 2
+const TITLED_MODES : [&str; 3] = ["quote", "wikipedia", "lessons"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub version : u32,
+    pub timestamp : u64,
+    pub mode : String,
+    pub file : String,
+    // Only for sources that pick a text themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title : Option<String>,
+    pub kind : String,
+    pub target : Count,
+    pub duration : f64,
+    pub wpm : f64,
+    pub raw : f64,
+    pub acc : f64,
+    pub right : Count,
+    pub wrong : Count,
+    pub extra : Count,
+    pub missed : Count,
+    // Missing in records older than transforms
+    #[serde(default)]
+    pub transforms : Transforms,
+    // Only for seeded sessions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed : Option<u64>,
+}
+
+impl Record {
+    pub fn new(punct : &Punct, mode : &str, file : &str, kind : TestKind) -> Record {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        let (kind, target) = match kind {
+            TestKind::Time(dur) => ("time", dur.as_secs()),
+            TestKind::Words(n_words) => ("words", n_words),
+            TestKind::Passage(n_words) => ("passage", n_words),
+        };
+
+        let (right, wrong, extra, missed) = punct.get_counts();
+
+        return Record {
+            version : VERSION,
+            timestamp,
+            mode : mode.to_string(),
+            file : file.to_string(),
+            title : punct.get_title(),
+            kind : kind.to_string(),
+            target,
+            duration : punct.get_total_time().as_secs_f64(),
+            wpm : punct.get_wpm(),
+            raw : punct.get_raw(),
+            acc : punct.get_acc(),
+            right,
+            wrong,
+            extra,
+            missed,
+            transforms : punct.get_transforms(),
+            seed : punct.get_seed(),
+        }
+    }
+
+    // Dictionary, file or command the words came from. The source of old
+    // records that saved a title instead is only known by its mode
+    pub fn source(&self) -> &str {
+        if self.version < 2 && TITLED_MODES.contains(&self.mode.as_str()) {
+            return &self.mode;
+        }
+        return &self.file;
+    }
+}
+
+pub fn append(path : &PathBuf, record : &Record) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let line = serde_json::to_string(record)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{line}")?;
+    return Ok(());
+}
+
+// A missing history is an empty one
+pub fn read(path : &PathBuf) -> io::Result<Vec<Record>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut records = Vec::new();
+    let reader = BufReader::new(fs::File::open(path)?);
+    for (n_line, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() { continue; }
+        match serde_json::from_str::<Record>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                let path = path.display();
+                let n_line = n_line + 1;
+                let error_msg = format!("{path}:{n_line}: {e}");
+                return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+            }
+        }
+    }
+    return Ok(records);
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{config::TestKind, punctuation::Punct, temp_dir::TempDir};
+
+    use super::{append, read, Record};
+
+    #[test]
+    fn append_and_read() {
+        let dir = TempDir::new("history");
+        let path = dir.join("history.jsonl");
+
+        assert!(read(&path).unwrap().is_empty());
+
+        let mut punct = Punct::new();
+        punct.set_total_time(Duration::from_secs(30));
+        let first = Record::new(&punct, "dictionary",
+            "100_english", TestKind::Time(Duration::from_secs(30)));
+        punct.set_title(Some(String::from("Keyboard")));
+        let second = Record::new(&punct, "wikipedia",
+            "enwiki.xml.bz2", TestKind::Words(25));
+
+        append(&path, &first).unwrap();
+        append(&path, &second).unwrap();
+
+        let records = read(&path).unwrap();
+
+        assert_eq!(records, vec![first, second]);
+        assert_eq!(records[0].file, "100_english");
+        assert_eq!(records[0].title, None);
+        assert_eq!(records[1].file, "enwiki.xml.bz2");
+        assert_eq!(records[1].title.as_deref(), Some("Keyboard"));
+        assert_eq!(records[0].kind, "time");
+        assert_eq!(records[0].target, 30);
+        assert_eq!(records[1].kind, "words");
+        assert_eq!(records[1].target, 25);
+        assert_eq!(records[1].duration, 30.0);
+    }
+
+    #[test]
+    fn records_without_transforms() {
+        let line = "{\"version\":1,\"timestamp\":1760781600,\"mode\":\"dictionary\",\"file\":\"100_english\",\"kind\":\"time\",\"target\":60,\"duration\":60.0,\"wpm\":72.4,\"raw\":75.1,\"acc\":0.964,\"right\":362,\"wrong\":13,\"extra\":2,\"missed\":1}";
+        let record : Record = serde_json::from_str(line).unwrap();
+        assert!(record.transforms.is_empty());
+        assert!(record.title.is_none());
+        assert_eq!(record.source(), "100_english");
+
+        let line = line.replace("\"dictionary\",\"file\":\"100_english\"", "\"quote\",\"file\":\"Ada Lovelace\"");
+        let record : Record = serde_json::from_str(&line).unwrap();
+        assert_eq!(record.source(), "quote");
+    }
+}
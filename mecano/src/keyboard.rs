@@ -0,0 +1,243 @@
+use std::{collections::HashMap, time::Duration};
+
+use crossterm::style::{Attribute, Color, SetAttribute, SetForegroundColor};
+use serde::Deserialize;
+
+use crate::{config::Theme, punctuation::KeyStats, TermUnit};
+
+const KEY_WIDTH : usize = 3;
+// Each row is shifted right from the previous one like in a real keyboard
+const ROW_OFFSETS : [usize; 4] = [0, 2, 3, 4];
+const SPACE_BAR_OFFSET : usize = 12;
+const SPACE_BAR_WIDTH : usize = 18;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    Qwerty,
+    Dvorak,
+    Colemak,
+    Azerty,
+}
+
+pub const ALL_LAYOUTS : &'static [&'static str] = &["qwerty", "dvorak", "colemak", "azerty"];
+
+// Unshifted and shifted chars of each row, key by key
+const QWERTY : [(&str, &str); 4] = [
+    ("1234567890-=", "!@#$%^&*()_+"),
+    ("qwertyuiop[]", "QWERTYUIOP{}"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+const DVORAK : [(&str, &str); 4] = [
+    ("1234567890[]", "!@#$%^&*(){}"),
+    ("',.pyfgcrl/=", "\"<>PYFGCRL?+"),
+    ("aoeuidhtns-", "AOEUIDHTNS_"),
+    (";qjkxbmwvz", ":QJKXBMWVZ"),
+];
+
+const COLEMAK : [(&str, &str); 4] = [
+    ("1234567890-=", "!@#$%^&*()_+"),
+    ("qwfpgjluy;[]", "QWFPGJLUY:{}"),
+    ("arstdhneio'", "ARSTDHNEIO\""),
+    ("zxcvbkm,./", "ZXCVBKM<>?"),
+];
+
+const AZERTY : [(&str, &str); 4] = [
+    ("&é\"'(-è_çà)=", "1234567890°+"),
+    ("azertyuiop^$", "AZERTYUIOP¨£"),
+    ("qsdfghjklmù*", "QSDFGHJKLM%µ"),
+    ("<wxcvbn,;:!", ">WXCVBN?./§"),
+];
+
+impl Layout {
+    pub fn new(s : &str) -> Option<Layout> {
+        return match s {
+            "qwerty" => Some(Layout::Qwerty),
+            "dvorak" => Some(Layout::Dvorak),
+            "colemak" => Some(Layout::Colemak),
+            "azerty" => Some(Layout::Azerty),
+            _ => None,
+        }
+    }
+
+    pub fn rows(&self) -> &'static [(&'static str, &'static str); 4] {
+        return match self {
+            Layout::Qwerty => &QWERTY,
+            Layout::Dvorak => &DVORAK,
+            Layout::Colemak => &COLEMAK,
+            Layout::Azerty => &AZERTY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HeatmapMetric {
+    Errors,
+    Latency,
+}
+
+impl HeatmapMetric {
+    fn title(&self) -> &'static str {
+        return match self {
+            HeatmapMetric::Errors => "ERROR RATE",
+            HeatmapMetric::Latency => "LATENCY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Heatmap {
+    layout : Layout,
+    metric : HeatmapMetric,
+    theme : Theme,
+}
+
+impl Heatmap {
+    pub fn new(layout : Layout, metric : HeatmapMetric, theme : Theme) -> Heatmap {
+        return Heatmap {
+            layout,
+            metric,
+            theme,
+        }
+    }
+
+    pub fn width(&self) -> TermUnit {
+        let width = self.layout.rows().iter()
+            .zip(ROW_OFFSETS)
+            .map(|((keys, _), offset)| offset + keys.chars().count() * KEY_WIDTH)
+            .max()
+            .unwrap_or(0);
+        return width as TermUnit;
+    }
+
+    // One line per keyboard row plus the title and the space bar
+    pub fn lines(&self, key_stats : &HashMap<char, KeyStats>) -> Vec<String> {
+        let mut values : Vec<Vec<Option<f64>>> = Vec::new();
+        for (keys, shifted_keys) in self.layout.rows() {
+            let row = keys.chars()
+                .zip(shifted_keys.chars())
+                .map(|(key, shifted)| self.value(key_stats, &[key, shifted]))
+                .collect();
+            values.push(row);
+        }
+        let space_value = self.value(key_stats, &[' ']);
+
+        // Colours go from the lowest value shown to the highest one
+        let all_values = values.iter().flatten().chain([&space_value]).flatten();
+        let mut min = all_values.clone().cloned().fold(f64::INFINITY, f64::min);
+        let max = all_values.cloned().fold(f64::NEG_INFINITY, f64::max);
+        // No errors at all is the best possible error rate
+        if let HeatmapMetric::Errors = self.metric {
+            min = 0.0;
+        }
+
+        let mut lines = vec![self.title(min, max)];
+        for (((keys, _), row), offset) in self.layout.rows().iter().zip(&values).zip(ROW_OFFSETS) {
+            let mut line = " ".repeat(offset);
+            for (key, value) in keys.chars().zip(row) {
+                line.push_str(&self.key_cell(&format!(" {key} "), *value, min, max));
+            }
+            lines.push(line);
+        }
+
+        let mut space_bar = " ".repeat(SPACE_BAR_OFFSET);
+        space_bar.push_str(&self.key_cell(&" ".repeat(SPACE_BAR_WIDTH), space_value, min, max));
+        lines.push(space_bar);
+
+        return lines;
+    }
+
+    fn title(&self, min : f64, max : f64) -> String {
+        let title = self.metric.title();
+        if min > max {
+            return title.to_string();
+        }
+        return match self.metric {
+            HeatmapMetric::Errors => 
+                format!("{title}  {:.0}% - {:.0}%", min * 100.0, max * 100.0),
+            HeatmapMetric::Latency => 
+                format!("{title}  {} - {}", 
+                    format_latency(Duration::from_secs_f64(min)),
+                    format_latency(Duration::from_secs_f64(max))),
+        }
+    }
+
+    fn value(&self, key_stats : &HashMap<char, KeyStats>, chars : &[char]) -> Option<f64> {
+        let mut merged = KeyStats::default();
+        for c in chars {
+            if let Some(stats) = key_stats.get(c) {
+                merged.merge(stats);
+            }
+        }
+        if merged.get_presses() == 0 {
+            return None;
+        }
+        return match self.metric {
+            HeatmapMetric::Errors => Some(merged.error_rate()),
+            HeatmapMetric::Latency => merged.mean_latency().map(|d| d.as_secs_f64()),
+        }
+    }
+
+    fn key_cell(&self, label : &str, value : Option<f64>, min : f64, max : f64) -> String {
+        if let Some(value) = value {
+            let t = if max > min { (value - min) / (max - min) } else { 0.0 };
+            let color = blend(self.theme.get_right(), self.theme.get_wrong(), t);
+            return format!("{}{}{label}{}{}",
+                SetForegroundColor(color),
+                SetAttribute(Attribute::Reverse),
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(Color::Reset));
+        } else {
+            return label.to_string();
+        }
+    }
+}
+
+// Linear blend between two rgb colours. Other colours just switch halfway
+fn blend(from : Color, to : Color, t : f64) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    if let (Color::Rgb { r : r0, g : g0, b : b0 }, Color::Rgb { r : r1, g : g1, b : b1 }) = (from, to) {
+        let mix = |a : u8, b : u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        return Color::Rgb { r : mix(r0, r1), g : mix(g0, g1), b : mix(b0, b1) };
+    } else if t < 0.5 {
+        return from;
+    } else {
+        return to;
+    }
+}
+
+fn format_latency(dur : Duration) -> String {
+    return format!("{}ms", dur.as_millis());
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::style::Color;
+
+    use super::{blend, Layout, ALL_LAYOUTS, KEY_WIDTH};
+
+    #[test]
+    fn layouts() {
+        for name in ALL_LAYOUTS {
+            let layout = Layout::new(name).unwrap();
+            for (keys, shifted_keys) in layout.rows() {
+                assert_eq!(keys.chars().count(), shifted_keys.chars().count());
+            }
+            assert!(layout.rows()[2].0.chars().count() * KEY_WIDTH > 30);
+        }
+        assert!(Layout::new("qwertz").is_none());
+    }
+
+    #[test]
+    fn blend_colors() {
+        let from = Color::Rgb { r : 0, g : 255, b : 0 };
+        let to = Color::Rgb { r : 255, g : 0, b : 0 };
+        assert_eq!(blend(from, to, 0.0), from);
+        assert_eq!(blend(from, to, 1.0), to);
+        assert_eq!(blend(from, to, 0.5), Color::Rgb { r : 128, g : 128, b : 0 });
+        assert_eq!(blend(Color::Green, Color::Red, 0.75), Color::Red);
+    }
+}
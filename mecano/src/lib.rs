@@ -14,6 +14,13 @@ pub mod mode;
 pub mod cli;
 pub mod textbox;
 pub mod punctuation;
+pub mod history;
+pub mod keyboard;
+pub mod graph;
+pub mod backend;
+pub mod session;
+#[cfg(test)]
+mod temp_dir;
 
 pub type Idx = usize;
 pub type TermUnit = u16;
@@ -2,6 +2,7 @@ use std::env;
 
 use mecano::cli::flags::flags;
 use mecano::cli::options::config_with_args;
+use mecano::cli::stats::stats;
 use mecano::engine::Mecano;
 
 fn main() {
@@ -14,6 +15,14 @@ fn main() {
         return;
     }
 
+    if args.get(1).is_some_and(|command| command == "stats") {
+        match stats(&args) {
+            Ok(stats_msg) => println!("{stats_msg}"),
+            Err(e) => eprintln!("{e}"),
+        }
+        return;
+    }
+
     let config = config_with_args(&args);
     if let Err(e) = &config {
         eprintln!("{e}");
@@ -24,8 +33,7 @@ fn main() {
     let result = Mecano::play(config);
 
     if let Err(e) = result {
-        let e_kind = e.kind();
-        eprintln!("Error during game : {e_kind}");
+        eprintln!("Error during game : {e}");
     }
 }
 
@@ -0,0 +1,278 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use rand::{distributions::{Distribution, WeightedIndex}, rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::adaptive_profile_path,
+    mode::{dictionary::read_dictionary, filter::WordFilter, rng, WordSource},
+    punctuation::{Punct, PunctWord},
+    Count
+};
+
+// Smallest weight given to a new key press in the moving averages
+const MIN_ALPHA : f64 = 0.05;
+// Weakness of keys and bigrams never typed, so they get explored
+const UNKNOWN_WEAKNESS : f64 = 0.5;
+// How much weakness weighs against picking words uniformly
+const WEAKNESS_FACTOR : f64 = 8.0;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    presses : Count,
+    // Moving averages
+    error : f64,
+    latency : f64,
+}
+
+impl Entry {
+    fn push(&mut self, miss : bool, latency : Option<f64>) {
+        self.presses += 1;
+        let alpha = (1.0 / self.presses as f64).max(MIN_ALPHA);
+        let error = if miss { 1.0 } else { 0.0 };
+        self.error += alpha * (error - self.error);
+        if let Some(latency) = latency {
+            if self.latency == 0.0 {
+                self.latency = latency;
+            } else {
+                self.latency += alpha * (latency - self.latency);
+            }
+        }
+    }
+}
+
+// Per key and per bigram errors and latencies, kept across sessions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Profile {
+    keys : HashMap<String, Entry>,
+    bigrams : HashMap<String, Entry>,
+}
+
+impl Profile {
+    fn load(path : &PathBuf) -> io::Result<Profile> {
+        if !path.exists() {
+            return Ok(Profile::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        return Ok(serde_json::from_str(&contents)?);
+    }
+
+    fn save(&self, path : &PathBuf) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return fs::write(path, serde_json::to_string(self)?);
+    }
+
+    fn feed(&mut self, punct_word : &PunctWord) {
+        let mut previous : Option<char> = None;
+        for key_press in punct_word.get_key_log() {
+            let aim = key_press.get_aim();
+            // Extra chars have no key to aim at
+            if aim == '\0' { continue; }
+
+            let miss = key_press.get_pressed() != aim;
+            let dur = key_press.get_dur();
+            let latency = if dur.is_zero() { None } else { Some(dur.as_secs_f64()) };
+
+            self.keys.entry(aim.to_string()).or_default().push(miss, latency);
+            if let Some(previous) = previous {
+                let bigram : String = [previous, aim].iter().collect();
+                self.bigrams.entry(bigram).or_default().push(miss, latency);
+            }
+            previous = Some(aim);
+        }
+    }
+
+    fn mean_latency(&self) -> f64 {
+        let latencies : Vec<f64> = self.keys.values()
+            .map(|e| e.latency)
+            .filter(|l| *l > 0.0)
+            .collect();
+        if latencies.is_empty() { return 0.0; }
+        return latencies.iter().sum::<f64>() / latencies.len() as f64;
+    }
+
+    // Errors plus how much slower than the mean latency
+    fn weakness(entry : Option<&Entry>, mean_latency : f64) -> f64 {
+        let Some(entry) = entry else { return UNKNOWN_WEAKNESS };
+        let mut slowness = 0.0;
+        if mean_latency > 0.0 && entry.latency > 0.0 {
+            slowness = (entry.latency / mean_latency - 1.0).max(0.0);
+        }
+        return entry.error + slowness;
+    }
+
+    fn word_weight(&self, word : &str, mean_latency : f64) -> f64 {
+        let chars : Vec<char> = word.chars().collect();
+        if chars.is_empty() { return 1.0; }
+
+        let key_weakness = chars.iter()
+            .map(|c| Profile::weakness(self.keys.get(&c.to_string()), mean_latency))
+            .sum::<f64>() / chars.len() as f64;
+
+        let mut bigram_weakness = 0.0;
+        if chars.len() > 1 {
+            bigram_weakness = chars.windows(2)
+                .map(|w| w.iter().collect::<String>())
+                .map(|b| Profile::weakness(self.bigrams.get(&b), mean_latency))
+                .sum::<f64>() / (chars.len() - 1) as f64;
+        }
+
+        return 1.0 + WEAKNESS_FACTOR * (key_weakness + bigram_weakness);
+    }
+}
+
+pub struct SourceAdaptive {
+    possible_words : Vec<String>,
+    profile : Profile,
+    profile_path : PathBuf,
+    weights : Option<WeightedIndex<f64>>,
+    rng : StdRng,
+}
+
+impl SourceAdaptive {
+    pub fn new(
+        path_to_dictionary : &PathBuf,
+        top : Option<Count>,
+        filter : &WordFilter,
+        profile_path : PathBuf,
+        seed : Option<u64>,
+    ) -> io::Result<SourceAdaptive> {
+        let possible_words : Vec<String> = read_dictionary(path_to_dictionary, top, filter)?
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect();
+
+        // A broken profile stops the game instead of being saved over
+        let profile = Profile::load(&profile_path).map_err(|e| {
+            let path = profile_path.display();
+            let error_msg = format!("invalid adaptive profile {path}: {e}");
+            return io::Error::new(io::ErrorKind::InvalidData, error_msg);
+        })?;
+
+        let mut source = SourceAdaptive {
+            possible_words,
+            profile,
+            profile_path,
+            weights : None,
+            rng : rng(seed),
+        };
+        source.update_weights();
+        return Ok(source);
+    }
+
+    fn update_weights(&mut self) {
+        let mean_latency = self.profile.mean_latency();
+        let weights : Vec<f64> = self.possible_words.iter()
+            .map(|w| self.profile.word_weight(w, mean_latency))
+            .collect();
+        self.weights = WeightedIndex::new(weights).ok();
+    }
+}
+
+impl WordSource for SourceAdaptive {
+    fn yield_word(&mut self) -> &str {
+        let idx = match &self.weights {
+            Some(weights) => weights.sample(&mut self.rng),
+            None => self.rng.gen_range(0..self.possible_words.len()),
+        };
+        return &self.possible_words[idx];
+    }
+
+    fn name(&self) -> String { String::from("adaptive") }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        return Self::new(
+            &config.get_file(),
+            config.get_top(),
+            &WordFilter::from_config(config)?,
+            adaptive_profile_path(),
+            config.get_seed(),
+        );
+    }
+
+    fn feed(&mut self, punct_word : &PunctWord) {
+        self.profile.feed(punct_word);
+        self.update_weights();
+    }
+
+    fn finish(&mut self, _punct : &Punct) -> io::Result<()> {
+        return self.profile.save(&self.profile_path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use crate::{mode::{filter::WordFilter, WordSource}, path_to_file, punctuation::{KeyPress, Punct, PunctWord}, temp_dir::TempDir};
+
+    use super::SourceAdaptive;
+
+    fn typed_word(word : &str, typed : &str) -> PunctWord {
+        let mut punct_word = PunctWord::new(word.len() as u64);
+        for (aim, pressed) in word.chars().zip(typed.chars()) {
+            punct_word.add_key_press(KeyPress::new(aim, pressed, Duration::from_millis(100)));
+        }
+        return punct_word;
+    }
+
+    #[test]
+    fn weak_keys_weigh_more() {
+        let dir = TempDir::new("adaptive");
+        let profile_path = dir.join("adaptive.json");
+
+        let mut source = SourceAdaptive::new(
+            &path_to_file("100_english").unwrap(), None, &WordFilter::default(), profile_path.clone(), None).unwrap();
+
+        for _ in 0..10 {
+            source.feed(&typed_word("the", "the"));
+            source.feed(&typed_word("you", "ypu"));
+        }
+
+        let mean_latency = source.profile.mean_latency();
+        let weak = source.profile.word_weight("you", mean_latency);
+        let strong = source.profile.word_weight("the", mean_latency);
+        assert!(weak > strong);
+
+        let with_o = source.possible_words.iter()
+            .filter(|w| w.contains('o'))
+            .count() as f64 / source.possible_words.len() as f64;
+        let yielded_with_o = (0..2000)
+            .filter(|_| source.yield_word().contains('o'))
+            .count() as f64 / 2000.0;
+        assert!(yielded_with_o > with_o);
+
+        source.finish(&Punct::new()).unwrap();
+        let reloaded = SourceAdaptive::new(
+            &path_to_file("100_english").unwrap(), None, &WordFilter::default(), profile_path.clone(), None).unwrap();
+        assert_eq!(reloaded.profile, source.profile);
+    }
+
+    #[test]
+    fn broken_profile_is_kept() {
+        let dir = TempDir::new("adaptive");
+        let profile_path = dir.join("adaptive.json");
+        std::fs::write(&profile_path, "{\"keys\":").unwrap();
+
+        assert!(SourceAdaptive::new(
+            &path_to_file("100_english").unwrap(), None, &WordFilter::default(), profile_path.clone(), None).is_err());
+        assert_eq!(std::fs::read_to_string(&profile_path).unwrap(), "{\"keys\":");
+    }
+
+    #[test]
+    fn seeded() {
+        let path = path_to_file("100_english").unwrap();
+        // No profile, so every source starts from the same weights
+        let new_source = |seed| SourceAdaptive::new(
+            &path, None, &WordFilter::default(), PathBuf::new(), Some(seed)).unwrap();
+        let mut source = new_source(42);
+        let mut same_seed = new_source(42);
+        let mut other_seed = new_source(43);
+
+        let words = source.yield_words();
+        assert_eq!(words, same_seed.yield_words());
+        assert_ne!(words, other_seed.yield_words());
+    }
+}
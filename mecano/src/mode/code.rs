@@ -0,0 +1,91 @@
+use std::{io, path::PathBuf};
+
+use crate::{mode::WordSource, Idx};
+
+// Ends a line in the words yielded by code mode
+pub const LINE_END : &str = "\n";
+
+pub struct SourceCode {
+    tokens : Vec<String>,
+    nth_token : Idx,
+}
+
+impl SourceCode {
+    pub fn new(path_to_file : &PathBuf) -> io::Result<SourceCode> {
+        let contents = std::fs::read_to_string(path_to_file).map_err(|e| {
+            let path = path_to_file.display();
+            io::Error::new(e.kind(), format!("couldn't read {path}: {e}"))
+        })?;
+        return SourceCode::from_text(&contents);
+    }
+
+    // The first word of each line keeps its indentation and each line is
+    // followed by a line end. Blank lines are left out
+    pub fn from_text(contents : &str) -> io::Result<SourceCode> {
+        let mut tokens : Vec<String> = Vec::new();
+        for line in contents.lines() {
+            let code = line.trim_start();
+            if code.trim().is_empty() { continue; }
+
+            let indent = &line[..line.len() - code.len()];
+            for (n_word, word) in code.split_whitespace().enumerate() {
+                if n_word == 0 {
+                    tokens.push(format!("{indent}{word}"));
+                } else {
+                    tokens.push(word.to_string());
+                }
+            }
+            tokens.push(LINE_END.to_string());
+        }
+
+        if tokens.is_empty() {
+            let error_msg = "no code to type";
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+        }
+
+        return Ok(SourceCode {
+            tokens,
+            nth_token : 0,
+        });
+    }
+}
+
+impl WordSource for SourceCode {
+    fn yield_word(&mut self) -> &str {
+        let token = &self.tokens[self.nth_token];
+        self.nth_token = (self.nth_token + 1) % self.tokens.len();
+        return token;
+    }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        if let Some(text) = config.get_text() {
+            return Self::from_text(&text);
+        }
+        return Self::new(&config.get_file());
+    }
+
+    fn name(&self) -> String { String::from("code") }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mode::WordSource;
+
+    use super::SourceCode;
+
+    #[test]
+    fn code_lines() {
+        let mut code = SourceCode::from_text(
+            "fn main() {\n\n    let x = 1;\n\tloop {}\n}\n").unwrap();
+
+        let tokens : Vec<String> = (0..13).map(|_| code.yield_word().to_string()).collect();
+        assert_eq!(tokens, vec![
+            "fn", "main()", "{", "\n",
+            "    let", "x", "=", "1;", "\n",
+            "\tloop", "{}", "\n",
+            "}",
+        ]);
+
+        assert!(SourceCode::from_text(" \n\t\n").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::{io, process::{Command, Stdio}};
+
+use crate::mode::{SourceFile, WordSource};
+
+pub struct SourceCommand {
+    file : SourceFile,
+}
+
+impl SourceCommand {
+    // Runs the command once and takes the words of its stdout
+    pub fn new(command : &str) -> io::Result<SourceCommand> {
+        let output = shell(command)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| {
+                let error_msg = format!("couldn't run command \"{command}\": {e}");
+                io::Error::new(e.kind(), error_msg)
+            })?;
+
+        if !output.status.success() {
+            let status = output.status;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stderr = stderr.trim();
+            let mut error_msg = format!("command \"{command}\" failed ({status})");
+            if !stderr.is_empty() {
+                error_msg.push_str(&format!(": {stderr}"));
+            }
+            return Err(io::Error::other(error_msg));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let file = SourceFile::from_text(&printable(&stdout));
+        if file.is_empty() {
+            let error_msg = format!("command \"{command}\" printed nothing");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+        }
+
+        return Ok(SourceCommand { file });
+    }
+}
+
+#[cfg(not(windows))]
+fn shell(command : &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    return shell;
+}
+
+#[cfg(windows)]
+fn shell(command : &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    return shell;
+}
+
+// Drops control chars and colour escape sequences. Backspaces erase the
+// previous char, as in the overstrike bold of man pages
+fn printable(text : &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' && chars.peek() == Some(&'[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() { break; }
+            }
+        } else if c == '\u{8}' {
+            clean.pop();
+        } else if !c.is_control() || c.is_whitespace() {
+            clean.push(c);
+        }
+    }
+    return clean;
+}
+
+impl WordSource for SourceCommand {
+    fn yield_word(&mut self) -> &str {
+        return self.file.yield_word();
+    }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        if let Some(command) = config.get_command() {
+            return Self::new(&command);
+        } else {
+            let error_msg = "command mode needs a command. Use --command <CMD>";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+        }
+    }
+
+    fn name(&self) -> String { String::from("command") }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mode::WordSource;
+
+    use super::{printable, SourceCommand};
+
+    #[test]
+    #[cfg(not(windows))]
+    fn command_words() {
+        let mut command = SourceCommand::new("echo hello  mecano; echo world").unwrap();
+        assert_eq!(command.yield_word(), "hello");
+        assert_eq!(command.yield_word(), "mecano");
+        assert_eq!(command.yield_word(), "world");
+        assert_eq!(command.yield_word(), "hello");
+
+        assert!(SourceCommand::new("true").is_err());
+        assert!(SourceCommand::new("echo words; exit 3").is_err());
+    }
+
+    #[test]
+    fn overstrike() {
+        assert_eq!(printable("N\u{8}NA\u{8}AM\u{8}ME\u{8}E\tls\u{1b}"), "NAME\tls");
+        assert_eq!(printable("\u{1b}[33mcommit\u{1b}[m abc"), "commit abc");
+    }
+}
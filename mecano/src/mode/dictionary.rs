@@ -1,64 +1,196 @@
-use std::path::PathBuf;
+use std::{io, path::PathBuf};
 
-use crate::{mode::WordSource, Idx};
+use rand::{distributions::{Distribution, WeightedIndex}, rngs::StdRng, seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{mode::{filter::WordFilter, rng, WordSource}, Count, Idx};
+
+// Draws allowed to find a word other than the last one. Only runs out when
+// almost all the weight is on a single word
+const MAX_REDRAWS : Count = 100;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Selection {
+    // Every draw is independent
+    Uniform,
+    // Every word once before any repeats
+    ShuffleBag,
+    // Never the same word twice in a row
+    NoRepeat,
+}
+
+pub const ALL_SELECTIONS : &'static [&'static str] = &["uniform", "shuffle-bag", "no-repeat"];
+
+impl Selection {
+    pub fn new(s : &str) -> Option<Selection> {
+        return match s {
+            "uniform" => Some(Selection::Uniform),
+            "shuffle-bag" => Some(Selection::ShuffleBag),
+            "no-repeat" => Some(Selection::NoRepeat),
+            _ => None,
+        }
+    }
+}
 
 pub struct SourceDictionary {
     possible_words: Vec<String>,
+    // Only for dictionaries with frequencies
+    weights : Option<WeightedIndex<f64>>,
+    selection : Selection,
+    // Words left in the shuffle bag
+    bag : Vec<Idx>,
+    last : Option<Idx>,
+    rng : StdRng,
 }
 
 impl SourceDictionary {
-    pub fn new(path_to_dictionary : &PathBuf) -> SourceDictionary {
-        let mut possible_words : Vec<String> = Vec::new();
-        let contents = std::fs::read_to_string(path_to_dictionary).unwrap();
+    pub fn new(
+        path_to_dictionary : &PathBuf,
+        top : Option<Count>,
+        filter : &WordFilter,
+        selection : Selection,
+        seed : Option<u64>,
+    ) -> io::Result<SourceDictionary> {
+        let entries = read_dictionary(path_to_dictionary, top, filter)?;
 
-        for word in contents.split_whitespace() {
-            possible_words.push(word.to_string());
+        let mut weights = None;
+        if entries.iter().any(|(_, weight)| *weight != entries[0].1) {
+            weights = WeightedIndex::new(entries.iter().map(|(_, weight)| *weight)).ok();
         }
 
-        if possible_words.is_empty() {
-            let error_msg = "No words in word_source";
-            eprintln!("{error_msg}");
-            panic!("{error_msg}");
+        return Ok(SourceDictionary {
+            possible_words : entries.into_iter().map(|(word, _)| word).collect(),
+            weights,
+            selection,
+            bag : Vec::new(),
+            last : None,
+            rng : rng(seed),
+        });
+    }
+
+    // Weighted by frequency when the dictionary has them
+    fn draw(&mut self) -> Idx {
+        return match &self.weights {
+            Some(weights) => weights.sample(&mut self.rng),
+            None => self.rng.gen_range(0..self.possible_words.len()),
         }
+    }
 
-        return SourceDictionary {
-            possible_words,
-        };
+    // Frequencies are left out, a bag holds every word once
+    fn draw_from_bag(&mut self) -> Idx {
+        if self.bag.is_empty() {
+            self.bag = (0..self.possible_words.len()).collect();
+            self.bag.shuffle(&mut self.rng);
+        }
+        return self.bag.pop().unwrap();
+    }
+
+    // Redraws while the word is the same as the last one. Dictionaries with
+    // the same word in several lines are taken into account
+    fn draw_other(&mut self) -> Idx {
+        let mut idx = self.draw();
+        let mut redraws = 0;
+        while let Some(last) = self.last {
+            if self.possible_words[idx] != self.possible_words[last] || redraws == MAX_REDRAWS {
+                break;
+            }
+            idx = self.draw();
+            redraws += 1;
+        }
+        return idx;
+    }
+}
+
+// Words of a dictionary with their weights. Lines are either plain words,
+// all weighing 1, or `word<TAB>weight`. With `top`, only the `top` heaviest
+// words passing the filter are kept, or the first ones when there are no
+// weights
+pub fn read_dictionary(
+    path_to_dictionary : &PathBuf,
+    top : Option<Count>,
+    filter : &WordFilter,
+) -> io::Result<Vec<(String, f64)>> {
+    let mut entries : Vec<(String, f64)> = Vec::new();
+    let contents = std::fs::read_to_string(path_to_dictionary).map_err(|e| {
+        let path = path_to_dictionary.display();
+        io::Error::new(e.kind(), format!("couldn't read {path}: {e}"))
+    })?;
+
+    for line in contents.lines() {
+        let weighted = line.split_once('\t')
+            .and_then(|(word, weight)| Some((word.trim(), weight.trim().parse::<f64>().ok()?)))
+            .filter(|(word, weight)| !word.is_empty() && *weight >= 0.0);
+
+        if let Some((word, weight)) = weighted {
+            entries.push((word.to_string(), weight));
+        } else {
+            for word in line.split_whitespace() {
+                entries.push((word.to_string(), 1.0));
+            }
+        }
+    }
+
+    entries.retain(|(word, _)| filter.matches(word));
+    if entries.is_empty() {
+        let path = path_to_dictionary.display();
+        let mut error_msg = format!("no words in {path}");
+        if !filter.is_empty() {
+            error_msg.push_str(" pass the filters");
+        }
+        return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+    }
+
+    if let Some(top) = top {
+        // Stable, so ties keep the order of the file
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1));
+        entries.truncate(top as usize);
     }
+    return Ok(entries);
 }
 
 impl WordSource for SourceDictionary {
     fn yield_word(&mut self) -> &str {
-        let word = self.possible_words
-            [random(self.possible_words.len() - 1)].as_str();
+        let idx = match self.selection {
+            Selection::Uniform => self.draw(),
+            Selection::ShuffleBag => self.draw_from_bag(),
+            Selection::NoRepeat => self.draw_other(),
+        };
+        self.last = Some(idx);
 
-        return word;
+        return self.possible_words[idx].as_str();
     }
 
     fn name(&self) -> String { String::from("dictionary") }
 
-    fn from_config(config : &crate::config::Config) -> Self {
-        return Self::new(&config.get_file());
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        return Self::new(
+            &config.get_file(),
+            config.get_top(),
+            &WordFilter::from_config(config)?,
+            config.get_selection(),
+            config.get_seed(),
+        );
     }
 }
 
-fn random(top : Idx) -> Idx {
-    return (rand::random::<f32>() * top as f32) as Idx;
-}
-
 #[cfg(test)]
 mod test {
 
     use crate::path_to_file;
 
-    use super::SourceDictionary;
+    use std::{collections::HashMap, fs, path::PathBuf};
+
+    use crate::{mode::filter::WordFilter, temp_dir::TempDir};
+
+    use super::{read_dictionary, Selection, SourceDictionary};
     use super::WordSource;
 
     #[test]
     fn printing_words() {
 
         let dict = SourceDictionary::new(
-            &path_to_file("100_spanish").unwrap());
+            &path_to_file("100_spanish").unwrap(), None, &WordFilter::default(), Selection::Uniform, None).unwrap();
 
         assert_eq!(dict.possible_words.len(), 100);
     }
@@ -67,11 +199,135 @@ mod test {
     fn true_random() {
 
         let mut dict = SourceDictionary::new(
-            &path_to_file("100_spanish").unwrap());
+            &path_to_file("100_spanish").unwrap(), None, &WordFilter::default(), Selection::Uniform, None).unwrap();
 
         let left_line = dict.yield_words();
         let right_line = dict.yield_words();
 
         assert!(!left_line.iter().eq(right_line.iter()));
     }
+
+    #[test]
+    fn frequency_weights() {
+        let dir = TempDir::new("frequencies");
+        let path = dir.join("frequencies");
+        std::fs::write(&path, "the\t500\nof\t300\nand\t200\nplain\nzebra\t0.5\n").unwrap();
+
+        let entries = read_dictionary(&path, None, &WordFilter::default()).unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[3], (String::from("plain"), 1.0));
+
+        let top = read_dictionary(&path, Some(2), &WordFilter::default()).unwrap();
+        assert_eq!(top, vec![(String::from("the"), 500.0), (String::from("of"), 300.0)]);
+
+        let mut dict = SourceDictionary::new(&path, Some(3), &WordFilter::default(), Selection::Uniform, None).unwrap();
+        let n_the = (0..1000).filter(|_| dict.yield_word() == "the").count();
+        assert!(n_the > 400 && n_the < 600);
+        assert!((0..100).all(|_| dict.yield_word() != "plain"));
+    }
+
+    #[test]
+    fn filtered_dictionary() {
+        let path = path_to_file("100_english").unwrap();
+        let short = WordFilter { max_length : Some(3), ..WordFilter::default() };
+        let dict = SourceDictionary::new(&path, None, &short, Selection::Uniform, None).unwrap();
+        assert!(dict.possible_words.iter().all(|w| w.chars().count() <= 3));
+
+        let nothing = WordFilter { min_length : Some(100), ..WordFilter::default() };
+        let error = SourceDictionary::new(&path, None, &nothing, Selection::Uniform, None).err().unwrap();
+        assert!(error.to_string().ends_with("pass the filters"));
+    }
+
+    #[test]
+    fn seeded() {
+        let path = path_to_file("100_spanish").unwrap();
+        let mut dict = SourceDictionary::new(&path, None, &WordFilter::default(), Selection::Uniform, Some(42)).unwrap();
+        let mut same_seed = SourceDictionary::new(&path, None, &WordFilter::default(), Selection::Uniform, Some(42)).unwrap();
+        let mut other_seed = SourceDictionary::new(&path, None, &WordFilter::default(), Selection::Uniform, Some(43)).unwrap();
+
+        let words = dict.yield_words();
+        assert_eq!(words, same_seed.yield_words());
+        assert_ne!(words, other_seed.yield_words());
+    }
+
+    fn builtin_dictionaries() -> Vec<PathBuf> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/dictionaries");
+        let mut paths : Vec<PathBuf> = fs::read_dir(dir).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        return paths;
+    }
+
+    // Times each line of the dictionary is drawn
+    fn draw_counts(dict : &mut SourceDictionary, n_draws : usize) -> Vec<usize> {
+        let mut counts = vec![0; dict.possible_words.len()];
+        for _ in 0..n_draws {
+            dict.yield_word();
+            counts[dict.last.unwrap()] += 1;
+        }
+        return counts;
+    }
+
+    #[test]
+    fn uniform_selection() {
+        for (n_dict, path) in builtin_dictionaries().iter().enumerate() {
+            let mut dict = SourceDictionary::new(path, None, &WordFilter::default(), Selection::Uniform, Some(n_dict as u64)).unwrap();
+            let n_words = dict.possible_words.len();
+            let expected = 200.0;
+            let counts = draw_counts(&mut dict, n_words * expected as usize);
+
+            // Every word, the last one too, can be drawn
+            assert!(counts.iter().all(|&count| count > 0), "{}", path.display());
+
+            // Pearson's chi-squared against the uniform distribution, far
+            // from the mean of n_words - 1 degrees of freedom only when biased
+            let chi_squared : f64 = counts.iter()
+                .map(|&count| (count as f64 - expected).powi(2) / expected)
+                .sum();
+            let freedom = (n_words - 1) as f64;
+            let bound = freedom + 5.0 * (2.0 * freedom).sqrt();
+            assert!(chi_squared < bound, "{}: {chi_squared} >= {bound}", path.display());
+        }
+    }
+
+    #[test]
+    fn shuffle_bag_selection() {
+        for path in builtin_dictionaries() {
+            let mut dict = SourceDictionary::new(&path, None, &WordFilter::default(), Selection::ShuffleBag, None).unwrap();
+            let n_words = dict.possible_words.len();
+
+            // Each round of the bag draws every line exactly once
+            for _ in 0..3 {
+                let counts = draw_counts(&mut dict, n_words);
+                assert!(counts.iter().all(|&count| count == 1), "{}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn no_repeat_selection() {
+        for path in builtin_dictionaries() {
+            let mut dict = SourceDictionary::new(&path, None, &WordFilter::default(), Selection::NoRepeat, None).unwrap();
+            let n_words = dict.possible_words.len();
+
+            let words : Vec<String> = (0..n_words * 50)
+                .map(|_| dict.yield_word().to_string())
+                .collect();
+            assert!(words.windows(2).all(|pair| pair[0] != pair[1]), "{}", path.display());
+
+            let mut seen : HashMap<&str, usize> = HashMap::new();
+            for word in &words {
+                *seen.entry(word).or_default() += 1;
+            }
+            assert_eq!(seen.len(), read_distinct(&path), "{}", path.display());
+        }
+    }
+
+    fn read_distinct(path : &PathBuf) -> usize {
+        let mut words : Vec<String> = read_dictionary(path, None, &WordFilter::default()).unwrap().into_iter().map(|(w, _)| w).collect();
+        words.sort();
+        words.dedup();
+        return words.len();
+    }
 }
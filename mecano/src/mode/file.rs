@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{io, path::PathBuf};
 
 use crate::{mode::WordSource, Idx};
 
@@ -9,7 +9,6 @@ pub struct SourceFile {
 
 impl SourceFile {
     pub fn new(path_to_file : &PathBuf) -> SourceFile {
-        let mut file_words : Vec<String> = Vec::new();
         let contents; 
         if let Ok(c) = std::fs::read_to_string(path_to_file) {
             contents = c;
@@ -18,6 +17,11 @@ impl SourceFile {
             panic!("file not found or corrupted");
         }
 
+        return SourceFile::from_text(&contents);
+    }
+
+    pub fn from_text(contents : &str) -> SourceFile {
+        let mut file_words : Vec<String> = Vec::new();
         for word in contents.split_whitespace() {
             file_words.push(word.to_string());
         }
@@ -27,6 +31,10 @@ impl SourceFile {
             nth_word : 0,
         };
     }
+
+    pub fn is_empty(&self) -> bool {
+        return self.file_words.is_empty();
+    }
 }
 
 impl WordSource for SourceFile {
@@ -36,8 +44,11 @@ impl WordSource for SourceFile {
         return word;
     }
 
-    fn from_config(config : &crate::config::Config) -> Self {
-        return Self::new(&config.get_file());
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        if let Some(text) = config.get_text() {
+            return Ok(Self::from_text(&text));
+        }
+        return Ok(Self::new(&config.get_file()));
     }
 
     fn name(&self) -> String { String::from("file") }
@@ -46,7 +57,7 @@ impl WordSource for SourceFile {
 #[cfg(test)]
 
 mod test {
-    use crate::{path_to_file, mode::WordSource};
+    use crate::{config::Config, path_to_file, mode::WordSource};
 
     use super::SourceFile;
 
@@ -64,4 +75,15 @@ mod test {
         }
         
     }
+
+    #[test]
+    fn text_from_stdin() {
+        let mut config = Config::default();
+        config.set_text("piped  text\nfrom stdin\n");
+        let mut mecano_file = SourceFile::from_config(&config).unwrap();
+
+        for word in ["piped", "text", "from", "stdin", "piped"] {
+            assert_eq!(word, mecano_file.yield_word());
+        }
+    }
 }
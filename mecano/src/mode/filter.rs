@@ -0,0 +1,212 @@
+use std::io;
+
+use regex::Regex;
+
+use crate::{config::Config, mode::WordSource, punctuation::{Punct, PunctWord}, Count};
+
+// Words looked at for one that passes the filters before giving up
+const MAX_SKIPPED : Count = 10_000;
+// Words that passed kept to be played again when the source runs dry
+const MAX_KEPT : usize = 1_000;
+
+// Which words are played. Letters are compared ignoring case
+#[derive(Debug, Clone, Default)]
+pub struct WordFilter {
+    pub min_length : Option<Count>,
+    pub max_length : Option<Count>,
+    // Words made only of these letters
+    pub only : Option<String>,
+    // Words with at least one of these letters
+    pub contains : Option<String>,
+    pub pattern : Option<Regex>,
+}
+
+impl WordFilter {
+    pub fn from_config(config : &Config) -> io::Result<WordFilter> {
+        let filter = WordFilter {
+            min_length : config.get_min_length(),
+            max_length : config.get_max_length(),
+            only : config.get_only().map(|only| only.to_lowercase()),
+            contains : config.get_contains().map(|contains| contains.to_lowercase()),
+            pattern : config.get_pattern().map(|p| Regex::new(&p)).transpose().map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidInput, format!("invalid pattern: {e}"))
+            })?,
+        };
+
+        if let (Some(min), Some(max)) = (filter.min_length, filter.max_length) {
+            if min > max {
+                let error_msg = format!("min length {min} is above max length {max}");
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+            }
+        }
+        return Ok(filter);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.min_length.is_none()
+            && self.max_length.is_none()
+            && self.only.is_none()
+            && self.contains.is_none()
+            && self.pattern.is_none();
+    }
+
+    pub fn matches(&self, word : &str) -> bool {
+        let n_chars = word.chars().count() as Count;
+        if self.min_length.is_some_and(|min| n_chars < min) {
+            return false;
+        }
+        if self.max_length.is_some_and(|max| n_chars > max) {
+            return false;
+        }
+
+        let lowercase = word.to_lowercase();
+        if let Some(only) = &self.only {
+            if !lowercase.chars().all(|c| only.contains(c)) {
+                return false;
+            }
+        }
+        if let Some(contains) = &self.contains {
+            if !lowercase.chars().any(|c| contains.contains(c)) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(word) {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+// Skips the words of another source that don't pass the filters
+pub struct SourceFiltered {
+    source : Box<dyn WordSource>,
+    filter : WordFilter,
+    word : String,
+    // The first word is found when building, to fail early
+    first_pending : bool,
+    matched : Vec<String>,
+    n_replayed : usize,
+}
+
+impl SourceFiltered {
+    pub fn new(source : Box<dyn WordSource>, filter : WordFilter) -> io::Result<SourceFiltered> {
+        let mut filtered = SourceFiltered {
+            source,
+            filter,
+            word : String::new(),
+            first_pending : true,
+            matched : Vec::new(),
+            n_replayed : 0,
+        };
+
+        if let Some(word) = filtered.next_match() {
+            filtered.word = word;
+            return Ok(filtered);
+        }
+        let error_msg = format!("no words of {} pass the filters", filtered.source.name());
+        return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+    }
+
+    fn next_match(&mut self) -> Option<String> {
+        for _ in 0..MAX_SKIPPED {
+            let word = self.source.yield_word();
+            if self.filter.matches(word) {
+                let word = word.to_string();
+                if self.matched.len() < MAX_KEPT {
+                    self.matched.push(word.clone());
+                }
+                return Some(word);
+            }
+        }
+        return None;
+    }
+}
+
+impl WordSource for SourceFiltered {
+    fn yield_word(&mut self) -> &str {
+        if self.first_pending {
+            self.first_pending = false;
+        } else if let Some(word) = self.next_match() {
+            self.word = word;
+        // The words that passed are played again instead of repeating the
+        // last one. There's at least the first one
+        } else {
+            self.word = self.matched[self.n_replayed % self.matched.len()].clone();
+            self.n_replayed += 1;
+        }
+        return &self.word;
+    }
+
+    fn name(&self) -> String { self.source.name() }
+
+    fn title(&self) -> Option<String> { self.source.title() }
+
+    fn passage_len(&self) -> Option<Count> { self.source.passage_len() }
+
+    fn feed(&mut self, punct_word : &PunctWord) {
+        self.source.feed(punct_word);
+    }
+
+    fn finish(&mut self, punct : &Punct) -> io::Result<()> {
+        return self.source.finish(punct);
+    }
+
+    fn from_config(_config : &Config) -> io::Result<Self> {
+        let error_msg = "filters wrap another source";
+        return Err(io::Error::new(io::ErrorKind::Unsupported, error_msg));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use regex::Regex;
+
+    use crate::mode::{SourceFile, WordSource};
+
+    use super::{SourceFiltered, WordFilter};
+
+    #[test]
+    fn filters() {
+        let home_row = WordFilter { only : Some(String::from("asdfghjkl")), ..WordFilter::default() };
+        assert!(home_row.matches("flask"));
+        assert!(home_row.matches("Salad"));
+        assert!(!home_row.matches("house"));
+
+        let lengths = WordFilter { min_length : Some(3), max_length : Some(4), ..WordFilter::default() };
+        assert!(!lengths.matches("to"));
+        assert!(lengths.matches("año"));
+        assert!(!lengths.matches("mecano"));
+
+        let contains = WordFilter { contains : Some(String::from("qz")), ..WordFilter::default() };
+        assert!(contains.matches("quick"));
+        assert!(!contains.matches("slow"));
+
+        let pattern = WordFilter { pattern : Some(Regex::new("^th").unwrap()), ..WordFilter::default() };
+        assert!(pattern.matches("the"));
+        assert!(!pattern.matches("with"));
+
+        assert!(WordFilter::default().is_empty());
+        assert!(!pattern.is_empty());
+
+        let mut source = SourceFiltered::new(
+            Box::new(SourceFile::from_text("a quick brown fox")), lengths.clone()).unwrap();
+        assert_eq!(source.yield_word(), "fox");
+        assert_eq!(source.yield_word(), "fox");
+
+        assert!(SourceFiltered::new(Box::new(SourceFile::from_text("a to")), lengths).is_err());
+    }
+
+    #[test]
+    fn replays_matches_when_source_runs_dry() {
+        let text = format!("fox cat {}", "to ".repeat(10_001));
+        let lengths = WordFilter { min_length : Some(3), max_length : Some(4), ..WordFilter::default() };
+        let mut source = SourceFiltered::new(
+            Box::new(SourceFile::from_text(&text)), lengths).unwrap();
+        assert_eq!(source.yield_word(), "fox");
+        assert_eq!(source.yield_word(), "cat");
+        // No match among the next words, the matches so far are played
+        assert_eq!(source.yield_word(), "fox");
+    }
+}
@@ -0,0 +1,352 @@
+use std::{fs, io, path::PathBuf};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::lessons_progress_path,
+    keyboard::Layout,
+    mode::{dictionary::read_dictionary, filter::WordFilter, rng, WordSource},
+    punctuation::Punct,
+    Count,
+};
+
+// Rows of the layouts, from the numbers row down
+const NUMBERS_ROW : usize = 0;
+const TOP_ROW : usize = 1;
+const HOME_ROW : usize = 2;
+const BOTTOM_ROW : usize = 3;
+
+// Fewer real words than these and pseudo-words are played
+const MIN_REAL_WORDS : usize = 10;
+const PSEUDO_WORD_LEN : (usize, usize) = (2, 6);
+// Percentage of dictionary words among pseudo-words, when there are enough
+const REAL_WORDS_RATE : u32 = 50;
+// Percentage of chars of pseudo-words taken from the new keys of the lesson
+const NEW_KEYS_RATE : u32 = 40;
+// Percentage of words changed once capitals, numbers or symbols are taught
+const SHIFT_RATE : u32 = 30;
+const NUMBERS_RATE : u32 = 20;
+const SYMBOLS_RATE : u32 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    HomeRow,
+    TopRow,
+    BottomRow,
+    Shift,
+    Numbers,
+    Symbols,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lesson {
+    pub name : &'static str,
+    stage : Stage,
+    pub min_wpm : f64,
+    pub min_acc : f64,
+}
+
+// Each lesson keeps the keys of the ones before it
+pub const LESSONS : [Lesson; 6] = [
+    Lesson { name : "home row", stage : Stage::HomeRow, min_wpm : 20.0, min_acc : 0.95 },
+    Lesson { name : "top row", stage : Stage::TopRow, min_wpm : 22.0, min_acc : 0.95 },
+    Lesson { name : "bottom row", stage : Stage::BottomRow, min_wpm : 22.0, min_acc : 0.95 },
+    Lesson { name : "shift", stage : Stage::Shift, min_wpm : 20.0, min_acc : 0.94 },
+    Lesson { name : "numbers", stage : Stage::Numbers, min_wpm : 18.0, min_acc : 0.93 },
+    Lesson { name : "symbols", stage : Stage::Symbols, min_wpm : 16.0, min_acc : 0.92 },
+];
+
+impl Lesson {
+    pub fn passed(&self, punct : &Punct) -> bool {
+        return punct.get_wpm() >= self.min_wpm && punct.get_acc() >= self.min_acc;
+    }
+
+    fn has(&self, stage : Stage) -> bool {
+        return self.stage as usize >= stage as usize;
+    }
+
+    // Lowercase letters of the rows taught so far and the ones new to
+    // this lesson
+    fn letters(&self, layout : Layout) -> (Vec<char>, Vec<char>) {
+        let rows : &[usize] = match self.stage {
+            Stage::HomeRow => &[HOME_ROW],
+            Stage::TopRow => &[HOME_ROW, TOP_ROW],
+            _ => &[HOME_ROW, TOP_ROW, BOTTOM_ROW],
+        };
+        let letters_of = |row : usize| -> Vec<char> {
+            return layout.rows()[row].0.chars().filter(|c| c.is_alphabetic()).collect();
+        };
+
+        let letters = rows.iter().flat_map(|row| letters_of(*row)).collect();
+        let new_letters = match self.stage {
+            Stage::HomeRow | Stage::TopRow | Stage::BottomRow => letters_of(*rows.last().unwrap()),
+            _ => Vec::new(),
+        };
+        return (letters, new_letters);
+    }
+}
+
+// Number of the lessons unlocked so far, the first one always is
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    unlocked : Count,
+}
+
+impl Progress {
+    pub fn load(path : &PathBuf) -> io::Result<Progress> {
+        if !path.exists() {
+            return Ok(Progress::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        return Ok(serde_json::from_str(&contents)?);
+    }
+
+    pub fn save(&self, path : &PathBuf) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        return fs::write(path, serde_json::to_string(self)?);
+    }
+
+    // Index of the last lesson unlocked
+    pub fn current(&self) -> usize {
+        return (self.unlocked as usize).min(LESSONS.len() - 1);
+    }
+
+    // Unlocks the next lesson when the latest one is passed
+    pub fn record(&mut self, n_lesson : usize, punct : &Punct) -> bool {
+        if n_lesson == self.current() && LESSONS[n_lesson].passed(punct) {
+            self.unlocked = (n_lesson + 1).min(LESSONS.len() - 1) as Count;
+            return n_lesson + 1 < LESSONS.len();
+        }
+        return false;
+    }
+}
+
+pub struct SourceLessons {
+    n_lesson : usize,
+    lesson : Lesson,
+    // Dictionary words typed only with the keys of the lesson
+    words : Vec<String>,
+    letters : Vec<char>,
+    new_letters : Vec<char>,
+    digits : Vec<char>,
+    symbols : Vec<char>,
+    progress_path : PathBuf,
+    rng : StdRng,
+    word : String,
+}
+
+impl SourceLessons {
+    pub fn new(
+        n_lesson : usize,
+        layout : Layout,
+        path_to_dictionary : &PathBuf,
+        progress_path : PathBuf,
+        seed : Option<u64>,
+    ) -> io::Result<SourceLessons> {
+        let lesson = LESSONS[n_lesson];
+        let (letters, new_letters) = lesson.letters(layout);
+
+        let only = WordFilter {
+            only : Some(letters.iter().collect()),
+            ..WordFilter::default()
+        };
+        // A dictionary without words of the lesson plays only pseudo-words
+        let words = read_dictionary(path_to_dictionary, None, &only)
+            .map(|entries| entries.into_iter().map(|(word, _)| word.to_lowercase()).collect())
+            .unwrap_or_default();
+
+        let (numbers, shifted_numbers) = layout.rows()[NUMBERS_ROW];
+        let digits = numbers.chars().chain(shifted_numbers.chars())
+            .filter(|c| c.is_ascii_digit())
+            .collect();
+        let symbols = layout.rows().iter()
+            .flat_map(|(keys, shifted)| keys.chars().chain(shifted.chars()))
+            .filter(|c| !c.is_alphanumeric())
+            .collect();
+
+        return Ok(SourceLessons {
+            n_lesson,
+            lesson,
+            words,
+            letters,
+            new_letters,
+            digits,
+            symbols,
+            progress_path,
+            rng : rng(seed),
+            word : String::new(),
+        });
+    }
+
+    fn happens(&mut self, rate : u32) -> bool {
+        return self.rng.gen_range(0..100) < rate;
+    }
+
+    fn pseudo_word(&mut self) -> String {
+        let len = self.rng.gen_range(PSEUDO_WORD_LEN.0..=PSEUDO_WORD_LEN.1);
+        let mut word = String::new();
+        for _ in 0..len {
+            let keys = if !self.new_letters.is_empty() && self.happens(NEW_KEYS_RATE) {
+                &self.new_letters
+            } else {
+                &self.letters
+            };
+            word.push(*keys.choose(&mut self.rng).unwrap());
+        }
+        return word;
+    }
+
+    fn number(&mut self) -> String {
+        let len = self.rng.gen_range(1..=4);
+        return (0..len).map(|_| *self.digits.choose(&mut self.rng).unwrap()).collect();
+    }
+}
+
+impl WordSource for SourceLessons {
+    fn yield_word(&mut self) -> &str {
+        let mut word = if self.words.len() >= MIN_REAL_WORDS && self.happens(REAL_WORDS_RATE) {
+            self.words.choose(&mut self.rng).unwrap().clone()
+        } else {
+            self.pseudo_word()
+        };
+
+        if self.lesson.has(Stage::Shift) && self.happens(SHIFT_RATE) {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                word = first.to_uppercase().chain(chars).collect();
+            }
+        }
+        if self.lesson.has(Stage::Numbers) && !self.digits.is_empty() && self.happens(NUMBERS_RATE) {
+            word = self.number();
+        }
+        if self.lesson.has(Stage::Symbols) && !self.symbols.is_empty() && self.happens(SYMBOLS_RATE) {
+            let symbol = *self.symbols.choose(&mut self.rng).unwrap();
+            if self.happens(50) {
+                word.insert(0, symbol);
+            } else {
+                word.push(symbol);
+            }
+        }
+
+        self.word = word;
+        return &self.word;
+    }
+
+    fn name(&self) -> String { String::from("lessons") }
+
+    fn title(&self) -> Option<String> {
+        let lesson = &self.lesson;
+        let n = self.n_lesson + 1;
+        let (name, wpm, acc) = (lesson.name, lesson.min_wpm, lesson.min_acc * 100.0);
+        return Some(format!("Lesson {n}: {name}, pass at {wpm:.0} WPM and {acc:.0}%"));
+    }
+
+    fn finish(&mut self, punct : &Punct) -> io::Result<()> {
+        // A broken progress file starts over instead of stopping the game
+        let mut progress = Progress::load(&self.progress_path).unwrap_or_default();
+        if progress.record(self.n_lesson, punct) {
+            progress.save(&self.progress_path)?;
+        }
+        return Ok(());
+    }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        let progress_path = lessons_progress_path();
+        let progress = Progress::load(&progress_path).unwrap_or_default();
+        let n_lesson = match config.get_lesson() {
+            Some(n) if n as usize > LESSONS.len() => {
+                let error_msg = format!("there are only {} lessons", LESSONS.len());
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+            }
+            Some(n) if n as usize > progress.current() + 1 => {
+                let error_msg = format!("lesson {n} is locked, pass lesson {} first", progress.current() + 1);
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, error_msg));
+            }
+            Some(n) => n as usize - 1,
+            None => progress.current(),
+        };
+        return Self::new(n_lesson, config.get_layout(), &config.get_file(), progress_path, config.get_seed());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        keyboard::Layout,
+        mode::WordSource,
+        path_to_file,
+        punctuation::{KeyPress, Punct, PunctWord},
+        temp_dir::TempDir,
+    };
+
+    use super::{Progress, SourceLessons, LESSONS};
+
+    // A test of `n_words` words typed in a minute, `wrong` of them with a miss
+    fn punct(n_words : usize, wrong : usize) -> Punct {
+        let mut punct = Punct::new();
+        for n_word in 0..n_words {
+            let mut punct_word = PunctWord::new(5);
+            for n_char in 0..5 {
+                let pressed = if n_word < wrong && n_char == 0 { 'x' } else { 'a' };
+                punct_word.add_key_press(KeyPress::new('a', pressed, Duration::from_millis(100)));
+            }
+            punct.push_punct_word(&punct_word);
+        }
+        punct.set_total_time(Duration::from_secs(60));
+        return punct;
+    }
+
+    #[test]
+    fn lesson_keys() {
+        let path = path_to_file("100_english").unwrap();
+        let dir = TempDir::new("lessons");
+        let progress_path = dir.join("lessons.json");
+
+        let mut home_row = SourceLessons::new(0, Layout::Qwerty, &path, progress_path.clone(), Some(1)).unwrap();
+        for _ in 0..200 {
+            assert!(home_row.yield_word().chars().all(|c| "asdfghjkl".contains(c)));
+        }
+
+        let mut shift = SourceLessons::new(3, Layout::Dvorak, &path, progress_path.clone(), Some(1)).unwrap();
+        let words : Vec<String> = (0..200).map(|_| shift.yield_word().to_string()).collect();
+        assert!(words.iter().all(|w| w.chars().all(char::is_alphabetic)));
+        assert!(words.iter().any(|w| w.starts_with(char::is_uppercase)));
+
+        let mut symbols = SourceLessons::new(5, Layout::Qwerty, &path, progress_path, Some(1)).unwrap();
+        let words : Vec<String> = (0..200).map(|_| symbols.yield_word().to_string()).collect();
+        assert!(words.iter().any(|w| w.chars().all(|c| c.is_ascii_digit())));
+        assert!(words.iter().any(|w| w.contains(|c : char| !c.is_alphanumeric())));
+    }
+
+    #[test]
+    fn lessons_unlock() {
+        let dir = TempDir::new("lessons");
+        let path = dir.join("lessons.json");
+        let mut progress = Progress::load(&path).unwrap();
+        assert_eq!(progress.current(), 0);
+
+        // 30 WPM with 90% accuracy, too many errors to pass
+        assert!(!progress.record(0, &punct(30, 15)));
+        assert_eq!(progress.current(), 0);
+
+        assert!(progress.record(0, &punct(30, 0)));
+        assert_eq!(progress.current(), 1);
+        // Replaying an earlier lesson doesn't unlock more
+        assert!(!progress.record(0, &punct(30, 0)));
+        assert_eq!(progress.current(), 1);
+
+        progress.save(&path).unwrap();
+        let reloaded = Progress::load(&path).unwrap();
+        assert_eq!(reloaded, progress);
+
+        for n_lesson in 1..LESSONS.len() {
+            progress.record(n_lesson, &punct(40, 0));
+        }
+        assert_eq!(progress.current(), LESSONS.len() - 1);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{io, path::PathBuf};
+
+use rand::{distributions::{Distribution, WeightedIndex}, rngs::StdRng};
+
+use crate::{mode::{dictionary::Selection, filter::WordFilter, rng, SourceDictionary, WordSource}, Count};
+
+// Draws each word from one of several dictionaries, chosen by their ratios
+pub struct SourceMix {
+    dictionaries : Vec<SourceDictionary>,
+    ratios : WeightedIndex<Count>,
+    rng : StdRng,
+}
+
+impl SourceMix {
+    pub fn new(
+        mix : &[(PathBuf, Count)],
+        top : Option<Count>,
+        filter : &WordFilter,
+        selection : Selection,
+        seed : Option<u64>,
+    ) -> io::Result<SourceMix> {
+        let ratios = WeightedIndex::new(mix.iter().map(|(_, ratio)| *ratio)).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("invalid dictionary mix: {e}"))
+        })?;
+
+        // Each dictionary gets its own seed so they don't draw in lockstep
+        let dictionaries = mix.iter()
+            .enumerate()
+            .map(|(n, (path, _))| {
+                let seed = seed.map(|seed| seed.wrapping_add(n as u64 + 1));
+                SourceDictionary::new(path, top, filter, selection, seed)
+            })
+            .collect::<io::Result<Vec<SourceDictionary>>>()?;
+
+        return Ok(SourceMix {
+            dictionaries,
+            ratios,
+            rng : rng(seed),
+        });
+    }
+}
+
+impl WordSource for SourceMix {
+    fn yield_word(&mut self) -> &str {
+        let idx = self.ratios.sample(&mut self.rng);
+        return self.dictionaries[idx].yield_word();
+    }
+
+    fn name(&self) -> String { String::from("dictionary") }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        return Self::new(
+            &config.get_mix(),
+            config.get_top(),
+            &WordFilter::from_config(config)?,
+            config.get_selection(),
+            config.get_seed(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{mode::{dictionary::Selection, filter::WordFilter, WordSource}, temp_dir::TempDir};
+
+    use super::SourceMix;
+
+    #[test]
+    fn mix_ratios() {
+        let dir = TempDir::new("mix");
+        let english = dir.join("english");
+        let spanish = dir.join("spanish");
+        std::fs::write(&english, "one two three").unwrap();
+        std::fs::write(&spanish, "uno dos tres").unwrap();
+
+        let mix = vec![(english.clone(), 3), (spanish.clone(), 1)];
+        let mut source = SourceMix::new(&mix, None, &WordFilter::default(), Selection::Uniform, Some(7)).unwrap();
+
+        let n_english = (0..4000)
+            .filter(|_| ["one", "two", "three"].contains(&source.yield_word()))
+            .count();
+        assert!(n_english > 2800 && n_english < 3200, "{n_english}");
+
+        assert!(SourceMix::new(&[], None, &WordFilter::default(), Selection::Uniform, None).is_err());
+    }
+}
@@ -1,12 +1,40 @@
 pub mod dictionary;
 pub mod file;
+pub mod adaptive;
+pub mod command;
+pub mod wikipedia;
+pub mod code;
+pub mod quote;
+pub mod transform;
+pub mod mix;
+pub mod filter;
+pub mod lessons;
+pub mod pseudo;
+pub mod replay;
 
 pub use file::SourceFile;
 pub use dictionary::SourceDictionary;
+pub use adaptive::SourceAdaptive;
+pub use command::SourceCommand;
+pub use wikipedia::SourceWikipedia;
+pub use code::SourceCode;
+pub use quote::SourceQuote;
+pub use transform::SourceTransformed;
+pub use mix::SourceMix;
+pub use filter::SourceFiltered;
+pub use lessons::SourceLessons;
+pub use pseudo::SourcePseudo;
+pub use replay::SourceReplay;
 
-use crate::{config::Config, TermUnit};
+use std::io;
 
-pub const ALL_MODES : &'static [&'static str] = &["dictionary", "file"];
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{config::Config, punctuation::{Punct, PunctWord}, Count, TermUnit};
+
+pub const ALL_MODES : &'static [&'static str] = &["dictionary", "file", "adaptive", "command", "wikipedia", "code", "quote", "lessons", "pseudo"];
+// Modes that can play text read from stdin
+pub const TEXT_MODES : &'static [&'static str] = &["file", "code"];
 
 pub fn all_modes_str() -> String {
     let mut all_modes_str = String::new();
@@ -21,6 +49,14 @@ pub fn all_modes_str() -> String {
     return all_modes_str;
 }
 
+// Seeded for reproducible sessions, random otherwise
+pub fn rng(seed : Option<u64>) -> StdRng {
+    return match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
 pub trait WordSource {
     fn yield_word(&mut self) -> &str;
 
@@ -40,5 +76,17 @@ pub trait WordSource {
 
     fn name(&self) -> String;
 
-    fn from_config(config : &Config) -> Self where Self : Sized;
+    // Title of the text, for sources that pick one themselves
+    fn title(&self) -> Option<String> { return None; }
+
+    // Number of words, for sources giving one whole passage to type once
+    fn passage_len(&self) -> Option<Count> { return None; }
+
+    // Called with every word once it is committed
+    fn feed(&mut self, _punct_word : &PunctWord) { }
+
+    // Called when the test ends, with its results
+    fn finish(&mut self, _punct : &Punct) -> io::Result<()> { return Ok(()); }
+
+    fn from_config(config : &Config) -> io::Result<Self> where Self : Sized;
 }
@@ -0,0 +1,197 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, io, path::PathBuf};
+
+use rand::{distributions::{Distribution, WeightedIndex}, rngs::StdRng};
+
+use crate::{mode::{dictionary::read_dictionary, filter::WordFilter, rng, WordSource}, Count};
+
+// Pads the start of words in the contexts and marks their end
+const START : char = '^';
+const END : char = '$';
+// Longest pseudo-word, so a model that rarely ends words can't run away
+const MAX_LEN : usize = 20;
+// Words generated to find one that passes the filters and isn't a real one
+const MAX_ATTEMPTS : Count = 1000;
+
+// Chars seen after each context of `order - 1` chars, with their weights
+struct Model {
+    order : usize,
+    next_chars : HashMap<String, (Vec<char>, WeightedIndex<f64>)>,
+}
+
+impl Model {
+    fn train(words : &[(String, f64)], order : usize) -> Model {
+        // Sorted chars, so the same seed makes the same words
+        let mut counts : HashMap<String, BTreeMap<char, f64>> = HashMap::new();
+        for (word, weight) in words {
+            let padded : Vec<char> = vec![START; order - 1].into_iter()
+                .chain(word.chars())
+                .chain(std::iter::once(END))
+                .collect();
+            for ngram in padded.windows(order) {
+                let context : String = ngram[..order - 1].iter().collect();
+                *counts.entry(context).or_default().entry(ngram[order - 1]).or_default() += weight;
+            }
+        }
+
+        let next_chars = counts.into_iter()
+            .filter_map(|(context, chars)| {
+                let (chars, weights) : (Vec<char>, Vec<f64>) = chars.into_iter().unzip();
+                let weights = WeightedIndex::new(weights).ok()?;
+                Some((context, (chars, weights)))
+            })
+            .collect();
+        return Model { order, next_chars };
+    }
+
+    fn generate(&self, rng : &mut StdRng) -> String {
+        let mut context : Vec<char> = vec![START; self.order - 1];
+        let mut word = String::new();
+        while word.chars().count() < MAX_LEN {
+            let key : String = context.iter().collect();
+            let next = match self.next_chars.get(&key) {
+                Some((chars, weights)) => chars[weights.sample(rng)],
+                None => END,
+            };
+            if next == END { break; }
+
+            word.push(next);
+            if !context.is_empty() {
+                context.remove(0);
+                context.push(next);
+            }
+        }
+        return word;
+    }
+}
+
+// Pronounceable words that don't exist, made with the letter statistics of
+// a dictionary or text
+pub struct SourcePseudo {
+    model : Model,
+    real_words : HashSet<String>,
+    filter : WordFilter,
+    rng : StdRng,
+    word : String,
+    // The first word is made when building, to fail early
+    first_pending : bool,
+}
+
+impl SourcePseudo {
+    pub fn new(
+        path_to_file : &PathBuf,
+        order : Count,
+        filter : WordFilter,
+        seed : Option<u64>,
+    ) -> io::Result<SourcePseudo> {
+        // Punctuation around the words of texts is left out
+        let words : Vec<(String, f64)> = read_dictionary(path_to_file, None, &WordFilter::default())?
+            .into_iter()
+            .map(|(word, weight)| (word.trim_matches(|c : char| !c.is_alphabetic()).to_lowercase(), weight))
+            .filter(|(word, _)| !word.is_empty())
+            .collect();
+
+        if words.is_empty() {
+            let path = path_to_file.display();
+            let error_msg = format!("no words to learn from in {path}");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+        }
+
+        let mut source = SourcePseudo {
+            model : Model::train(&words, order.max(1) as usize),
+            real_words : words.into_iter().map(|(word, _)| word).collect(),
+            filter,
+            rng : rng(seed),
+            word : String::new(),
+            first_pending : true,
+        };
+
+        if let Some(word) = source.next_word() {
+            source.word = word;
+            return Ok(source);
+        }
+        let error_msg = "no pseudo-words pass the filters";
+        return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+    }
+
+    // Real words are only let through when the model can't make up others
+    fn next_word(&mut self) -> Option<String> {
+        let mut fallback = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let word = self.model.generate(&mut self.rng);
+            if word.is_empty() || !self.filter.matches(&word) {
+                continue;
+            }
+            if !self.real_words.contains(&word) {
+                return Some(word);
+            }
+            fallback = Some(word);
+        }
+        return fallback;
+    }
+}
+
+impl WordSource for SourcePseudo {
+    fn yield_word(&mut self) -> &str {
+        if self.first_pending {
+            self.first_pending = false;
+        } else if let Some(word) = self.next_word() {
+            self.word = word;
+        }
+        return &self.word;
+    }
+
+    fn name(&self) -> String { String::from("pseudo") }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        let file = match config.get_text() {
+            Some(_) => {
+                let error_msg = "pseudo mode learns from a file, not from stdin";
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+            }
+            None => config.get_file(),
+        };
+        return Self::new(&file, config.get_ngram(), WordFilter::from_config(config)?, config.get_seed());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{mode::{filter::WordFilter, WordSource}, path_to_file, temp_dir::TempDir};
+
+    use super::SourcePseudo;
+
+    #[test]
+    fn pseudo_words() {
+        let dir = TempDir::new("pseudo");
+        let path = dir.join("pseudo");
+        std::fs::write(&path, "banana, bandana! cabana\nanna").unwrap();
+
+        let mut source = SourcePseudo::new(&path, 3, WordFilter::default(), Some(3)).unwrap();
+        for _ in 0..100 {
+            let word = source.yield_word();
+            assert!(word.chars().all(|c| "abncd".contains(c)), "{word}");
+            // Every pair of letters was seen in the text
+            let pairs = ["ba", "an", "na", "nd", "da", "ca", "ab", "nn"];
+            assert!(word.as_bytes().windows(2).all(|p| pairs.contains(&std::str::from_utf8(p).unwrap())), "{word}");
+        }
+
+        let path = path_to_file("300_english").unwrap();
+        let lengths = WordFilter { min_length : Some(4), max_length : Some(7), ..WordFilter::default() };
+        let mut source = SourcePseudo::new(&path, 3, lengths.clone(), Some(3)).unwrap();
+        let mut same_seed = SourcePseudo::new(&path, 3, lengths, Some(3)).unwrap();
+        let words = source.yield_words();
+        assert!(words.iter().all(|w| (4..=7).contains(&w.chars().count())));
+        assert!(words.iter().all(|w| !source.real_words.contains(w)));
+        assert_eq!(words, same_seed.yield_words());
+
+        let impossible = WordFilter { min_length : Some(50), ..WordFilter::default() };
+        assert!(SourcePseudo::new(&path, 3, impossible, None).is_err());
+
+        // The word checked when building is the first one played
+        let long = WordFilter { min_length : Some(9), ..WordFilter::default() };
+        let mut source = SourcePseudo::new(&path, 3, long, Some(5)).unwrap();
+        let checked = source.word.clone();
+        assert!(checked.chars().count() >= 9);
+        assert_eq!(source.yield_word(), checked);
+    }
+}
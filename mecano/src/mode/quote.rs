@@ -0,0 +1,171 @@
+use std::{fs, io, path::PathBuf};
+
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::{cli::quotes_path, mode::{rng, SourceFile, WordSource}, Count};
+
+// Longest passage of each length class, in chars
+const SHORT_CHARS : usize = 100;
+const MEDIUM_CHARS : usize = 250;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteLength {
+    Short,
+    Medium,
+    Long,
+    Any,
+}
+
+pub const ALL_QUOTE_LENGTHS : &'static [&'static str] = &["short", "medium", "long", "any"];
+
+impl QuoteLength {
+    pub fn new(s : &str) -> Option<QuoteLength> {
+        return match s {
+            "short" => Some(QuoteLength::Short),
+            "medium" => Some(QuoteLength::Medium),
+            "long" => Some(QuoteLength::Long),
+            "any" => Some(QuoteLength::Any),
+            _ => None,
+        }
+    }
+
+    fn of_text(text : &str) -> QuoteLength {
+        let n_chars = text.chars().count();
+        if n_chars <= SHORT_CHARS {
+            return QuoteLength::Short;
+        } else if n_chars <= MEDIUM_CHARS {
+            return QuoteLength::Medium;
+        } else {
+            return QuoteLength::Long;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Quote {
+    pub text : String,
+    pub author : String,
+    #[serde(default)]
+    pub source : String,
+    // Worked out from the text when missing
+    pub length : Option<QuoteLength>,
+}
+
+impl Quote {
+    pub fn get_length(&self) -> QuoteLength {
+        return self.length.unwrap_or(QuoteLength::of_text(&self.text));
+    }
+
+    pub fn attribution(&self) -> String {
+        if self.source.is_empty() {
+            return self.author.clone();
+        }
+        return format!("{}, {}", self.author, self.source);
+    }
+}
+
+pub struct SourceQuote {
+    quote : Quote,
+    file : SourceFile,
+}
+
+impl SourceQuote {
+    pub fn new(quote : Quote) -> SourceQuote {
+        let file = SourceFile::from_text(&quote.text);
+        return SourceQuote { quote, file };
+    }
+
+    // A random quote of the chosen length among all the quote files
+    pub fn from_dir(dir : &PathBuf, length : QuoteLength, seed : Option<u64>) -> io::Result<SourceQuote> {
+        let quotes : Vec<Quote> = read_quotes(dir)?
+            .into_iter()
+            .filter(|q| length == QuoteLength::Any || q.get_length() == length)
+            .filter(|q| !q.text.trim().is_empty())
+            .collect();
+
+        if let Some(quote) = quotes.choose(&mut rng(seed)) {
+            return Ok(SourceQuote::new(quote.clone()));
+        } else {
+            let dir = dir.display();
+            let error_msg = format!("no {length:?} quotes found in {dir}").to_lowercase();
+            return Err(io::Error::new(io::ErrorKind::NotFound, error_msg));
+        }
+    }
+}
+
+// Every .json file of the directory holds a list of quotes
+pub fn read_quotes(dir : &PathBuf) -> io::Result<Vec<Quote>> {
+    let mut quotes = Vec::new();
+    if !dir.exists() {
+        return Ok(quotes);
+    }
+
+    // Sorted so seeded sessions pick the same quote
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<PathBuf>>>()?;
+    paths.sort();
+
+    for path in paths {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let contents = fs::read_to_string(&path)?;
+        let file_quotes : Vec<Quote> = serde_json::from_str(&contents).map_err(|e| {
+            let path = path.display();
+            io::Error::new(io::ErrorKind::InvalidData, format!("{path}: {e}"))
+        })?;
+        quotes.extend(file_quotes);
+    }
+    return Ok(quotes);
+}
+
+impl WordSource for SourceQuote {
+    fn yield_word(&mut self) -> &str {
+        return self.file.yield_word();
+    }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        return Self::from_dir(&quotes_path(), config.get_quote_length(), config.get_seed());
+    }
+
+    fn name(&self) -> String { String::from("quote") }
+
+    fn title(&self) -> Option<String> { Some(self.quote.attribution()) }
+
+    fn passage_len(&self) -> Option<Count> {
+        return Some(self.quote.text.split_whitespace().count() as Count);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::mode::WordSource;
+
+    use super::{read_quotes, QuoteLength, SourceQuote};
+
+    #[test]
+    fn quote_lengths() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/quotes");
+        let quotes = read_quotes(&dir).unwrap();
+        assert!(!quotes.is_empty());
+        for quote in &quotes {
+            assert_eq!(quote.length, Some(QuoteLength::of_text(&quote.text)));
+        }
+
+        for length in [QuoteLength::Short, QuoteLength::Medium, QuoteLength::Long] {
+            let source = SourceQuote::from_dir(&dir, length, None).unwrap();
+            assert_eq!(source.quote.get_length(), length);
+            assert!(source.title().unwrap().starts_with(&source.quote.author));
+        }
+
+        let mut source = SourceQuote::new(quotes[0].clone());
+        let words : Vec<String> = quotes[0].text.split_whitespace().map(String::from).collect();
+        assert_eq!(source.passage_len(), Some(words.len() as u64));
+        assert_eq!(source.yield_word(), words[0]);
+    }
+}
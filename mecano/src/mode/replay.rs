@@ -0,0 +1,76 @@
+use std::io;
+
+use crate::{config::Config, mode::WordSource, punctuation::{Punct, PunctWord}, Count, Idx};
+
+// Yields again the words of a test, then goes on with a fresh source when
+// the retry goes further than the test did
+pub struct SourceReplay {
+    words : Vec<String>,
+    nth_word : Idx,
+    title : Option<String>,
+    passage_len : Option<Count>,
+    source : Box<dyn WordSource>,
+}
+
+impl SourceReplay {
+    pub fn new(
+        words : &[String],
+        title : Option<String>,
+        passage_len : Option<Count>,
+        source : Box<dyn WordSource>,
+    ) -> SourceReplay {
+        return SourceReplay {
+            words : words.to_vec(),
+            nth_word : 0,
+            title,
+            passage_len,
+            source,
+        };
+    }
+}
+
+impl WordSource for SourceReplay {
+    fn yield_word(&mut self) -> &str {
+        if let Some(word) = self.words.get(self.nth_word) {
+            self.nth_word += 1;
+            return word;
+        }
+        return self.source.yield_word();
+    }
+
+    fn name(&self) -> String { self.source.name() }
+
+    fn title(&self) -> Option<String> { self.title.clone() }
+
+    fn passage_len(&self) -> Option<Count> { self.passage_len }
+
+    // Adaptive and lessons modes keep learning from retries
+    fn feed(&mut self, punct_word : &PunctWord) {
+        self.source.feed(punct_word);
+    }
+
+    fn finish(&mut self, punct : &Punct) -> io::Result<()> {
+        return self.source.finish(punct);
+    }
+
+    fn from_config(_config : &Config) -> io::Result<Self> {
+        let error_msg = "replays are made from a finished test";
+        return Err(io::Error::new(io::ErrorKind::Unsupported, error_msg));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mode::{SourceFile, WordSource};
+
+    use super::SourceReplay;
+
+    #[test]
+    fn replay() {
+        let played = vec![String::from("one"), String::from("two")];
+        let mut source = SourceReplay::new(
+            &played, None, None, Box::new(SourceFile::from_text("three four")));
+        let words : Vec<String> = (0..4).map(|_| source.yield_word().to_string()).collect();
+        assert_eq!(words, vec!["one", "two", "three", "four"]);
+    }
+}
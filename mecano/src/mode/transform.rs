@@ -0,0 +1,236 @@
+use std::{collections::VecDeque, fmt::Display, io};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{mode::{code::LINE_END, rng, WordSource}, punctuation::{Punct, PunctWord}, Count};
+
+// Commas are the most common, sentence ends less so
+const PUNCTUATION : [&str; 8] = [",", ",", ",", ".", ".", "?", "!", ";"];
+const SENTENCE_ENDS : [char; 3] = ['.', '?', '!'];
+const BRACKETS : [(&str, &str); 5] = [
+    ("\"", "\""),
+    ("'", "'"),
+    ("(", ")"),
+    ("[", "]"),
+    ("{", "}"),
+];
+const MAX_NUMBER_DIGITS : u32 = 4;
+// Keeps the draws of the transforms apart from the ones of the source
+const SEED_OFFSET : u64 = 0x9e37_79b9_7f4a_7c15;
+
+// Percentage of words changed by each transform
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transforms {
+    pub capitals : Count,
+    pub punctuation : Count,
+    pub brackets : Count,
+    pub numbers : Count,
+}
+
+impl Transforms {
+    pub fn is_empty(&self) -> bool {
+        return *self == Transforms::default();
+    }
+}
+
+impl Display for Transforms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let transforms = [
+            ("CAPITALS", self.capitals),
+            ("PUNCTUATION", self.punctuation),
+            ("BRACKETS", self.brackets),
+            ("NUMBERS", self.numbers),
+        ];
+        let shown : Vec<String> = transforms.iter()
+            .filter(|(_, rate)| *rate > 0)
+            .map(|(name, rate)| format!("{name} {rate}%"))
+            .collect();
+        return write!(f, "{}", shown.join("  "));
+    }
+}
+
+// Capitalises, punctuates or wraps the words of any other source, and
+// inserts numbers among them
+pub struct SourceTransformed {
+    source : Box<dyn WordSource>,
+    transforms : Transforms,
+    rng : StdRng,
+    word : String,
+    sentence_start : bool,
+    // Source word played after the number inserted before it
+    pending : Option<String>,
+    // Passages draw upfront which words get a number, so their length
+    // counts the numbers
+    planned_numbers : VecDeque<bool>,
+    n_planned_numbers : Count,
+}
+
+impl SourceTransformed {
+    pub fn new(source : Box<dyn WordSource>, transforms : Transforms, seed : Option<u64>) -> SourceTransformed {
+        let mut transformed = SourceTransformed {
+            source,
+            transforms,
+            rng : rng(seed.map(|seed| seed ^ SEED_OFFSET)),
+            word : String::new(),
+            sentence_start : false,
+            pending : None,
+            planned_numbers : VecDeque::new(),
+            n_planned_numbers : 0,
+        };
+        if let Some(passage_len) = transformed.source.passage_len() {
+            for _ in 0..passage_len {
+                let number = transformed.happens(transforms.numbers);
+                transformed.planned_numbers.push_back(number);
+            }
+            transformed.n_planned_numbers = transformed.planned_numbers.iter()
+                .filter(|number| **number)
+                .count() as Count;
+        }
+        return transformed;
+    }
+
+    fn number_before(&mut self) -> bool {
+        return match self.planned_numbers.pop_front() {
+            Some(number) => number,
+            None => self.happens(self.transforms.numbers),
+        }
+    }
+
+    fn happens(&mut self, rate : Count) -> bool {
+        return rate > 0 && self.rng.gen_range(0..100) < rate;
+    }
+
+    fn transform(&mut self, word : &str) -> String {
+        // Indentation of code mode is kept as it is
+        let trimmed = word.trim_start();
+        let indent = &word[..word.len() - trimmed.len()];
+        let mut word = trimmed.to_string();
+
+        // Words after a sentence end are capitalised too
+        if self.sentence_start || self.happens(self.transforms.capitals) {
+            word = capitalise(&word);
+        }
+
+        if self.happens(self.transforms.brackets) {
+            let (open, close) = BRACKETS.choose(&mut self.rng).unwrap();
+            word = format!("{open}{word}{close}");
+        }
+
+        self.sentence_start = false;
+        if self.happens(self.transforms.punctuation) {
+            let mark = PUNCTUATION.choose(&mut self.rng).unwrap();
+            word.push_str(mark);
+            self.sentence_start = word.ends_with(SENTENCE_ENDS);
+        }
+
+        return format!("{indent}{word}");
+    }
+}
+
+fn capitalise(word : &str) -> String {
+    let mut chars = word.chars();
+    return match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl WordSource for SourceTransformed {
+    fn yield_word(&mut self) -> &str {
+        if let Some(word) = self.pending.take() {
+            self.word = self.transform(&word);
+            return &self.word;
+        }
+
+        let word = self.source.yield_word().to_string();
+        if word == LINE_END {
+            self.word = word;
+        } else if self.number_before() {
+            // The number takes the indentation of code mode
+            let trimmed = word.trim_start();
+            let indent = &word[..word.len() - trimmed.len()];
+            let digits = self.rng.gen_range(1..=MAX_NUMBER_DIGITS);
+            let number = self.rng.gen_range(0..10u64.pow(digits));
+            self.pending = Some(trimmed.to_string());
+            self.word = self.transform(&format!("{indent}{number}"));
+        } else {
+            self.word = self.transform(&word);
+        }
+        return &self.word;
+    }
+
+    fn name(&self) -> String { self.source.name() }
+
+    fn title(&self) -> Option<String> { self.source.title() }
+
+    fn passage_len(&self) -> Option<Count> {
+        return self.source.passage_len().map(|len| len + self.n_planned_numbers);
+    }
+
+    fn feed(&mut self, punct_word : &PunctWord) {
+        self.source.feed(punct_word);
+    }
+
+    fn finish(&mut self, punct : &Punct) -> io::Result<()> {
+        return self.source.finish(punct);
+    }
+
+    fn from_config(_config : &crate::config::Config) -> io::Result<Self> {
+        let error_msg = "transforms wrap another source";
+        return Err(io::Error::new(io::ErrorKind::Unsupported, error_msg));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mode::{SourceCode, SourceFile, SourceReplay, WordSource};
+
+    use super::{capitalise, SourceTransformed, Transforms};
+
+    #[test]
+    fn transforms() {
+        let every_word = Transforms { capitals : 100, punctuation : 100, brackets : 100, numbers : 0 };
+        let mut source = SourceTransformed::new(
+            Box::new(SourceFile::from_text("mecano")), every_word, None);
+        for _ in 0..20 {
+            let word = source.yield_word().to_string();
+            let inner = word.trim_end_matches([',', '.', '?', '!', ';']);
+            assert!(inner.len() == "mecano".len() + 2, "{word}");
+            assert_eq!(&inner[1..inner.len() - 1], "Mecano");
+        }
+
+        // Numbers are inserted before the words, not instead of them
+        let numbers = Transforms { numbers : 100, ..Transforms::default() };
+        let mut source = SourceTransformed::new(
+            Box::new(SourceCode::from_text("  let x\n").unwrap()), numbers, None);
+        let indented = source.yield_word().to_string();
+        assert!(indented.starts_with("  "));
+        assert!(indented.trim_start().parse::<u64>().is_ok());
+        assert_eq!(source.yield_word(), "let");
+        assert!(source.yield_word().parse::<u64>().is_ok());
+        assert_eq!(source.yield_word(), "x");
+        assert_eq!(source.yield_word(), "\n");
+
+        // Passages count the numbers they get
+        let half = Transforms { numbers : 50, ..Transforms::default() };
+        let words = ["to", "be", "or", "not"].map(String::from);
+        let quote = SourceReplay::new(&words, None, Some(4), Box::new(SourceFile::from_text("unused")));
+        let mut source = SourceTransformed::new(Box::new(quote), half, Some(1));
+        let passage_len = source.passage_len().unwrap();
+        let words = (0..passage_len).map(|_| source.yield_word().to_string()).collect::<Vec<String>>();
+        let n_numbers = words.iter().filter(|w| w.parse::<u64>().is_ok()).count() as u64;
+        assert_eq!(passage_len, 4 + n_numbers);
+        assert_eq!(words.iter().filter(|w| w.parse::<u64>().is_err()).collect::<Vec<&String>>(), vec!["to", "be", "or", "not"]);
+
+        let none = Transforms::default();
+        assert!(none.is_empty());
+        let mut source = SourceTransformed::new(
+            Box::new(SourceFile::from_text("a b")), none, None);
+        assert_eq!(source.yield_word(), "a");
+        assert_eq!(source.yield_word(), "b");
+
+        assert_eq!(capitalise("émile"), "Émile");
+        assert_eq!(every_word.to_string(), "CAPITALS 100%  PUNCTUATION 100%  BRACKETS 100%");
+    }
+}
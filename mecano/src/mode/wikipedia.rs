@@ -0,0 +1,604 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use bzip2::read::{BzDecoder, MultiBzDecoder};
+use rand::{rngs::StdRng, Rng};
+
+use crate::mode::{rng, SourceFile, WordSource};
+
+// Stubs and lists are too short to practise on
+const MIN_ARTICLE_BYTES : usize = 2000;
+// Streams of a multistream dump looked at for a long enough article
+const MAX_STREAMS_TRIED : usize = 50;
+
+pub struct Article {
+    pub title : String,
+    pub text : String,
+}
+
+pub struct SourceWikipedia {
+    title : String,
+    file : SourceFile,
+}
+
+impl SourceWikipedia {
+    // Takes the article with the chosen title or a random one from a
+    // Wikipedia XML dump, plain or .bz2, or from a directory of extracted
+    // plaintext articles
+    pub fn new(path : &Path, title : Option<&str>, seed : Option<u64>) -> io::Result<SourceWikipedia> {
+        let mut rng = rng(seed);
+        let article = if path.is_dir() {
+            article_from_dir(path, title, &mut rng)?
+        } else if let Some(index) = multistream_index(path) {
+            article_from_multistream(path, &index, title, &mut rng)?
+        } else {
+            article_from_dump(path, title, &mut rng)?
+        };
+
+        let Some(article) = article else {
+            let path = path.display();
+            let error_msg = match title {
+                Some(title) => format!("article \"{title}\" not found in {path}"),
+                None => format!("no articles found in {path}"),
+            };
+            return Err(io::Error::new(io::ErrorKind::NotFound, error_msg));
+        };
+
+        let file = SourceFile::from_text(&article.text);
+        if file.is_empty() {
+            let error_msg = format!("article \"{}\" has no text", article.title);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, error_msg));
+        }
+
+        return Ok(SourceWikipedia { title : article.title, file });
+    }
+}
+
+impl WordSource for SourceWikipedia {
+    fn yield_word(&mut self) -> &str {
+        return self.file.yield_word();
+    }
+
+    fn from_config(config : &crate::config::Config) -> io::Result<Self> {
+        if let Some(path) = config.get_wikipedia() {
+            return Self::new(&path, config.get_article().as_deref(), config.get_seed());
+        } else {
+            let error_msg = "wikipedia mode needs a dump. Use --wikipedia <PATH>";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, error_msg));
+        }
+    }
+
+    fn name(&self) -> String { String::from("wikipedia") }
+
+    fn title(&self) -> Option<String> { Some(self.title.clone()) }
+}
+
+fn article_from_dump(path : &Path, title : Option<&str>, rng : &mut StdRng) -> io::Result<Option<Article>> {
+    let file = File::open(path)?;
+    let reader : Box<dyn Read> = match path.extension() {
+        Some(ext) if ext == "bz2" => Box::new(MultiBzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+
+    let article = pick_page(BufReader::new(reader), title, rng)?;
+    return Ok(article.map(stripped));
+}
+
+fn stripped(article : Article) -> Article {
+    return Article { text : strip_markup(&article.text), title : article.title };
+}
+
+// Multistream dumps like enwiki-latest-pages-articles-multistream.xml.bz2
+// come with an index of the stream holding each page, named
+// enwiki-latest-pages-articles-multistream-index.txt.bz2
+fn multistream_index(path : &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    let index_name = format!("{}-index.txt.bz2", file_name.strip_suffix(".xml.bz2")?);
+    let index = path.with_file_name(index_name);
+    return index.is_file().then_some(index);
+}
+
+// Only the stream of the article is decompressed instead of the whole dump.
+// A random article is drawn from a random stream, streams hold about the
+// same number of pages
+fn article_from_multistream(
+    path : &Path,
+    index : &Path,
+    title : Option<&str>,
+    rng : &mut StdRng,
+) -> io::Result<Option<Article>> {
+    let mut offsets : Vec<u64> = Vec::new();
+    let index_reader = BufReader::new(MultiBzDecoder::new(File::open(index)?));
+    for line in index_reader.lines() {
+        let line = line?;
+        // offset:page id:title, titles may hold colons too
+        let mut fields = line.splitn(3, ':');
+        let (Some(offset), Some(_), Some(page_title)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let Ok(offset) = offset.parse::<u64>() else {
+            continue;
+        };
+
+        if let Some(title) = title {
+            if unescape(page_title) == title {
+                let stream = read_stream(path, offset)?;
+                return Ok(pick_page(stream, Some(title), rng)?.map(stripped));
+            }
+        } else if offsets.last() != Some(&offset) {
+            offsets.push(offset);
+        }
+    }
+
+    for _ in 0..MAX_STREAMS_TRIED {
+        if offsets.is_empty() {
+            break;
+        }
+        let offset = offsets.swap_remove(rng.gen_range(0..offsets.len()));
+        if let Some(article) = pick_page(read_stream(path, offset)?, None, rng)? {
+            return Ok(Some(stripped(article)));
+        }
+    }
+    return Ok(None);
+}
+
+fn read_stream(path : &Path, offset : u64) -> io::Result<BufReader<BzDecoder<File>>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    return Ok(BufReader::new(BzDecoder::new(file)));
+}
+
+// Streams the pages of the dump. Without a title, one article is drawn
+// uniformly through reservoir sampling, so the dump is read only once
+fn pick_page(reader : impl BufRead, title : Option<&str>, rng : &mut StdRng) -> io::Result<Option<Article>> {
+    let mut picked : Option<Article> = None;
+    let mut n_articles = 0;
+
+    let mut page = Page::default();
+    let mut in_text = false;
+    for line in reader.lines() {
+        let line = line?;
+        let trimmed = line.trim_start();
+
+        if in_text {
+            if let Some(end) = line.find("</text>") {
+                page.text.push_str(&line[..end]);
+                in_text = false;
+            } else {
+                page.text.push_str(&line);
+                page.text.push('\n');
+            }
+        } else if trimmed.starts_with("<page>") {
+            page = Page::default();
+        } else if let Some(page_title) = tag_content(trimmed, "title") {
+            page.title = unescape(page_title);
+        } else if let Some(ns) = tag_content(trimmed, "ns") {
+            page.namespace = ns.to_string();
+        } else if trimmed.starts_with("<redirect") {
+            page.redirect = true;
+        } else if trimmed.starts_with("<text") && !trimmed.ends_with("/>") {
+            let start = trimmed.find('>').map_or(trimmed.len(), |i| i + 1);
+            let rest = &trimmed[start..];
+            if let Some(end) = rest.find("</text>") {
+                page.text.push_str(&rest[..end]);
+            } else {
+                page.text.push_str(rest);
+                page.text.push('\n');
+                in_text = true;
+            }
+        } else if trimmed.starts_with("</page>") {
+            let page = std::mem::take(&mut page);
+            if page.namespace != "0" || page.redirect {
+                continue;
+            }
+
+            if let Some(title) = title {
+                if page.title == title {
+                    return Ok(Some(page.into_article()));
+                }
+            } else if page.text.len() >= MIN_ARTICLE_BYTES {
+                n_articles += 1;
+                if rng.gen_range(0..n_articles) == 0 {
+                    picked = Some(page.into_article());
+                }
+            }
+        }
+    }
+    return Ok(picked);
+}
+
+#[derive(Default)]
+struct Page {
+    title : String,
+    namespace : String,
+    redirect : bool,
+    // Still XML escaped
+    text : String,
+}
+
+impl Page {
+    fn into_article(self) -> Article {
+        return Article { title : self.title, text : unescape(&self.text) };
+    }
+}
+
+fn tag_content<'a>(line : &'a str, tag : &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(&format!("<{tag}>"))?;
+    let end = rest.find(&format!("</{tag}>"))?;
+    return Some(&rest[..end]);
+}
+
+fn unescape(text : &str) -> String {
+    return text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+}
+
+// Keeps the prose of an article written in wikitext. Templates, tables,
+// references, files and categories are dropped and links keep their label
+pub fn strip_markup(wikitext : &str) -> String {
+    let text = remove_nested(wikitext, "{{", "}}");
+    let text = remove_nested(&text, "{|", "|}");
+    let text = remove_between(&text, "<!--", "-->");
+    let text = remove_refs(&text);
+    let text = replace_links(&text);
+    let text = remove_tags(&text);
+
+    let mut prose = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        // Headings, lists and leftovers of tables
+        if line.starts_with('=') || line.starts_with('|') || line.starts_with('!') {
+            continue;
+        }
+        let line = line.trim_start_matches(['*', '#', ':', ';', ' ']);
+        let line = line.replace("'''", "").replace("''", "");
+        if !line.trim().is_empty() {
+            prose.push_str(line.trim());
+            prose.push('\n');
+        }
+    }
+    return prose;
+}
+
+fn remove_nested(text : &str, open : &str, close : &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        if rest.starts_with(open) {
+            depth += 1;
+            rest = &rest[open.len()..];
+        } else if depth > 0 && rest.starts_with(close) {
+            depth -= 1;
+            rest = &rest[close.len()..];
+        } else {
+            let c = rest.chars().next().unwrap();
+            if depth == 0 {
+                clean.push(c);
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    return clean;
+}
+
+fn remove_between(text : &str, open : &str, close : &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(open) {
+        clean.push_str(&rest[..start]);
+        match rest[start..].find(close) {
+            Some(end) => rest = &rest[start + end + close.len()..],
+            None => rest = "",
+        }
+    }
+    clean.push_str(rest);
+    return clean;
+}
+
+// Both <ref name="a"/> and <ref>...</ref>
+fn remove_refs(text : &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<ref") {
+        clean.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let Some(tag_end) = tag.find('>') else {
+            rest = "";
+            break;
+        };
+        if tag[..tag_end].ends_with('/') {
+            rest = &tag[tag_end + 1..];
+        } else {
+            match tag.find("</ref>") {
+                Some(end) => rest = &tag[end + "</ref>".len()..],
+                None => rest = "",
+            }
+        }
+    }
+    clean.push_str(rest);
+    return clean;
+}
+
+fn remove_tags(text : &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => clean.push(c),
+            _ => (),
+        }
+    }
+    return clean;
+}
+
+// [[Target|label]] keeps the label, [[Target]] the target and
+// [http://url label] the label. Files and categories are dropped
+fn replace_links(text : &str) -> String {
+    let mut clean = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        clean.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("[[") {
+            let inner_len = matching_link_end(&rest[2..]);
+            let inner = &rest[2..2 + inner_len];
+            rest = &rest[(2 + inner_len + 2).min(rest.len())..];
+
+            let target = inner.split('|').next().unwrap_or("");
+            let namespace = target.split(':').next().unwrap_or("").trim().to_lowercase();
+            if target.contains(':') && ["file", "image", "category"].contains(&namespace.as_str()) {
+                continue;
+            }
+            clean.push_str(inner.rsplit('|').next().unwrap_or(""));
+        } else if let Some(end) = rest.find(']') {
+            let inner = &rest[1..end];
+            if inner.starts_with("http") || inner.starts_with("//") {
+                if let Some((_, label)) = inner.split_once(' ') {
+                    clean.push_str(label);
+                }
+            } else {
+                clean.push_str(&rest[..=end]);
+            }
+            rest = &rest[end + 1..];
+        } else {
+            clean.push_str(rest);
+            rest = "";
+        }
+    }
+    clean.push_str(rest);
+    return clean;
+}
+
+// Length of the inside of a [[link]], which may hold other links
+fn matching_link_end(text : &str) -> usize {
+    let mut depth = 1;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("[[") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("]]") {
+            depth -= 1;
+            if depth == 0 {
+                return i;
+            }
+            i += 2;
+        } else {
+            i += text[i..].chars().next().map_or(1, |c| c.len_utf8());
+        }
+    }
+    return text.len();
+}
+
+// A random file of the directory, with a random article of the file.
+// Files made by WikiExtractor hold several <doc title="..."> articles,
+// any other file is one article named after the file
+fn article_from_dir(dir : &Path, title : Option<&str>, rng : &mut StdRng) -> io::Result<Option<Article>> {
+    let mut files = Vec::new();
+    files_in_dir(dir, &mut files)?;
+    files.sort();
+
+    if let Some(title) = title {
+        for file in &files {
+            let article = articles_in_file(file)?
+                .into_iter()
+                .find(|article| article.title == title);
+            if article.is_some() {
+                return Ok(article);
+            }
+        }
+        return Ok(None);
+    }
+
+    while !files.is_empty() {
+        let file = files.swap_remove(rng.gen_range(0..files.len()));
+        let mut articles = articles_in_file(&file)?;
+        if !articles.is_empty() {
+            return Ok(Some(articles.swap_remove(rng.gen_range(0..articles.len()))));
+        }
+    }
+    return Ok(None);
+}
+
+fn files_in_dir(dir : &Path, files : &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            files_in_dir(&entry.path(), files)?;
+        } else {
+            files.push(entry.path());
+        }
+    }
+    return Ok(());
+}
+
+fn articles_in_file(path : &Path) -> io::Result<Vec<Article>> {
+    let contents = fs::read_to_string(path)?;
+    let mut articles = Vec::new();
+
+    if !contents.trim_start().starts_with("<doc") {
+        if !contents.trim().is_empty() {
+            let title = path.file_stem().map_or(String::new(), |s| s.to_string_lossy().to_string());
+            articles.push(Article { title, text : contents });
+        }
+        return Ok(articles);
+    }
+
+    for doc in contents.split("</doc>") {
+        let Some(start) = doc.find("<doc") else { continue };
+        let doc = &doc[start..];
+        let Some(header_end) = doc.find('>') else { continue };
+        let header = &doc[..header_end];
+        let title = header.split("title=\"")
+            .nth(1)
+            .and_then(|t| t.split('"').next())
+            .map_or(String::new(), unescape);
+        let text = doc[header_end + 1..].trim();
+        // WikiExtractor repeats the title as the first line
+        let text = text.strip_prefix(title.as_str()).unwrap_or(text).to_string();
+        if !text.trim().is_empty() {
+            articles.push(Article { title, text });
+        }
+    }
+    return Ok(articles);
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use bzip2::{write::BzEncoder, Compression};
+
+    use crate::{mode::{rng, WordSource}, temp_dir::TempDir};
+
+    use super::{pick_page, strip_markup, SourceWikipedia};
+
+    const DUMP : &str = "<mediawiki>
+  <page>
+    <title>Keyboard</title>
+    <ns>0</ns>
+    <revision>
+      <text bytes=\"120\" xml:space=\"preserve\">{{Infobox|name=x}}
+'''Keyboards''' have [[key (instrument)|keys]] and [[switch]]es.&lt;ref&gt;A book&lt;/ref&gt;
+== History ==
+[[File:Board.jpg|thumb|A [[board]]]]
+See [https://example.org typewriters].
+[[Category:Devices]]</text>
+    </revision>
+  </page>
+  <page>
+    <title>Keys</title>
+    <ns>0</ns>
+    <redirect title=\"Keyboard\" />
+    <revision>
+      <text bytes=\"20\">#REDIRECT [[Keyboard]]</text>
+    </revision>
+  </page>
+  <page>
+    <title>Talk:Keyboard</title>
+    <ns>1</ns>
+    <revision>
+      <text bytes=\"5\">talk</text>
+    </revision>
+  </page>
+</mediawiki>
+";
+
+    #[test]
+    fn markup() {
+        let text = strip_markup("{{Infobox|a={{b}}}}'''Mecano''' is a [[typing|typing test]]\
+            <ref name=\"a\"/> for [[terminal]]s.<!-- hidden -->\n\
+            {|\n| cell\n|}\n* [[Category:Games]]");
+        assert_eq!(text, "Mecano is a typing test for terminals.\n");
+    }
+
+    #[test]
+    fn dump_article() {
+        let article = pick_page(DUMP.as_bytes(), Some("Keyboard"), &mut rng(None)).unwrap().unwrap();
+        assert!(article.text.contains("<ref>"));
+        assert_eq!(strip_markup(&article.text),
+            "Keyboards have keys and switches.\nSee typewriters.\n");
+
+        assert!(pick_page(DUMP.as_bytes(), Some("Keys"), &mut rng(None)).unwrap().is_none());
+        assert!(pick_page(DUMP.as_bytes(), Some("Talk:Keyboard"), &mut rng(None)).unwrap().is_none());
+        // Too short to be drawn at random
+        assert!(pick_page(DUMP.as_bytes(), None, &mut rng(None)).unwrap().is_none());
+    }
+
+    #[test]
+    fn dump_and_dir_sources() {
+        let dir = TempDir::new("wikipedia");
+        std::fs::create_dir_all(dir.join("extracted/AA")).unwrap();
+
+        let dump = dir.join("dump.xml.bz2");
+        let mut encoder = BzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(DUMP.as_bytes()).unwrap();
+        std::fs::write(&dump, encoder.finish().unwrap()).unwrap();
+
+        std::fs::write(dir.join("extracted/AA/wiki_00"),
+            "<doc id=\"1\" url=\"u\" title=\"Typing\">\nTyping\n\nTyping is writing.\n</doc>\n").unwrap();
+
+        let mut source = SourceWikipedia::new(&dump, Some("Keyboard"), None).unwrap();
+        assert_eq!(source.title(), Some(String::from("Keyboard")));
+        assert_eq!(source.yield_word(), "Keyboards");
+
+        let mut source = SourceWikipedia::new(&dir.join("extracted"), None, None).unwrap();
+        assert_eq!(source.title(), Some(String::from("Typing")));
+        assert_eq!(source.yield_word(), "Typing");
+        assert_eq!(source.yield_word(), "is");
+    }
+
+    fn bz2(text : &str) -> Vec<u8> {
+        let mut encoder = BzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(text.as_bytes()).unwrap();
+        return encoder.finish().unwrap();
+    }
+
+    fn page(title : &str, text : &str) -> String {
+        return format!("  <page>\n    <title>{title}</title>\n    <ns>0</ns>\n    <revision>\n      \
+            <text xml:space=\"preserve\">{text}</text>\n    </revision>\n  </page>\n");
+    }
+
+    #[test]
+    fn multistream_dump() {
+        let dir = TempDir::new("wikipedia");
+        let dump = dir.join("wiki-multistream.xml.bz2");
+        let long = "Words of a long article. ".repeat(100);
+
+        // One stream per page, and pages missing from the index can't be found
+        let streams = [
+            bz2(&format!("<mediawiki>\n{}", page("Typing", "Too short"))),
+            bz2(&page("Keyboard", &long)),
+            bz2(&format!("{}</mediawiki>\n", page("Hidden", &long))),
+        ];
+        let keyboard_offset = streams[0].len();
+        std::fs::write(&dump, streams.concat()).unwrap();
+        let index = format!("0:1:Typing\n{keyboard_offset}:2:Keyboard\n");
+        std::fs::write(dir.join("wiki-multistream-index.txt.bz2"), bz2(&index)).unwrap();
+
+        let source = SourceWikipedia::new(&dump, Some("Keyboard"), None).unwrap();
+        assert_eq!(source.title(), Some(String::from("Keyboard")));
+        assert!(SourceWikipedia::new(&dump, Some("Hidden"), None).is_err());
+        for seed in 0..5 {
+            let source = SourceWikipedia::new(&dump, None, Some(seed)).unwrap();
+            assert_eq!(source.title(), Some(String::from("Keyboard")));
+        }
+
+        // Without its index, the dump is read whole
+        std::fs::remove_file(dir.join("wiki-multistream-index.txt.bz2")).unwrap();
+        assert!(SourceWikipedia::new(&dump, Some("Hidden"), None).is_ok());
+    }
+}
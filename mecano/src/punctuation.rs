@@ -1,15 +1,29 @@
 use std::{
+    cmp::min,
     collections::HashMap,
     fmt::Display,
     time::Duration
 };
 
-use crossterm::cursor::{MoveDown, MoveTo};
+use crossterm::{
+    cursor::MoveTo,
+    style::{Color, SetForegroundColor},
+};
 
-use crate::{Count, TermUnit};
+use crate::{
+    config::Theme,
+    graph::{Graph, Sample},
+    keyboard::Heatmap,
+    mode::transform::Transforms,
+    Count, TermUnit
+};
 
 const SECS_PER_MIN : f64 = 60.0;
 const CHARS_PER_WORD : f64 = 5.0;
+const N_KEYS_SHOWN : usize = 5;
+const GRAPH_MAX_WIDTH : TermUnit = 80;
+const GRAPH_MAX_HEIGHT : TermUnit = 10;
+const GRAPH_MIN_HEIGHT : TermUnit = 4;
 
 #[derive(Debug, Clone)]
 pub struct Punct {
@@ -17,11 +31,19 @@ pub struct Punct {
     chars_wrong : Count,
     chars_extra : Count,
     chars_missed : Count,
+    // One entry per typed word
     stats : Vec<Stats>,
-    mapped_key_presses : HashMap<char, KeyPress>,
+    final_stats : Option<Stats>,
+    key_stats : HashMap<char, KeyStats>,
     total_time : Duration,
     size : (TermUnit, TermUnit),
     pos : (TermUnit, TermUnit),
+    heatmap : Option<Heatmap>,
+    theme : Theme,
+    // Attribution or article of the typed text
+    title : Option<String>,
+    transforms : Transforms,
+    seed : Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +51,9 @@ struct Stats {
     wpm : f64,
     raw : f64,
     acc : f64,
+    time : Duration,
+    right : Count,
+    wrong : Count,
 }
 
 impl Punct {
@@ -39,20 +64,25 @@ impl Punct {
             chars_extra : 0,
             chars_missed : 0,
             stats : Vec::new(),
-            mapped_key_presses : HashMap::new(),
+            final_stats : None,
+            key_stats : HashMap::new(),
             total_time : Duration::ZERO,
             size : (0, 0),
             pos : (0, 0),
+            heatmap : None,
+            theme : Theme::default(),
+            title : None,
+            transforms : Transforms::default(),
+            seed : None,
         }
     }
 
     // Test purpouse
     pub fn get_raw_info(&self) -> 
     (Count, Count, Count, Count, f64, f64, f64) {
-        let final_stats = self.stats.iter().last().unwrap();
-        let raw = final_stats.raw;
-        let wpm = final_stats.wpm;
-        let acc = final_stats.acc;
+        let raw = self.get_raw();
+        let wpm = self.get_wpm();
+        let acc = self.get_acc();
         (self.chars_right, self.chars_wrong, self.chars_extra, self.chars_missed,
              raw, wpm, acc)
     }
@@ -63,30 +93,105 @@ impl Punct {
         self.chars_wrong += punct_word.wrong;            
         self.chars_missed += punct_word.missed;            
         for key_press in &punct_word.key_presses {
-            let key_press = key_press.clone();
-            self.mapped_key_presses.insert(key_press.pressed, key_press);
             self.total_time += key_press.dur;
         }
-        self.stats.push(self.calc_stats());
+        for key_press in &punct_word.key_log {
+            // Extra chars have no key to aim at
+            if key_press.aim == '\0' { continue; }
+            self.key_stats.entry(key_press.aim)
+                .or_default()
+                .push(key_press);
+        }
+        if !punct_word.key_log.is_empty() {
+            self.stats.push(self.calc_stats());
+        }
+    }
+
+    pub fn get_key_stats(&self) -> &HashMap<char, KeyStats> {
+        return &self.key_stats;
+    }
+
+    // Keys sorted by mean latency, slowest first
+    pub fn slowest_keys(&self) -> Vec<(char, &KeyStats)> {
+        let mut keys : Vec<(char, &KeyStats)> = self.key_stats.iter()
+            .filter(|(_, k)| k.mean_latency().is_some())
+            .map(|(c, k)| (*c, k))
+            .collect();
+        keys.sort_by(|(a_c, a), (b_c, b)| b.mean_latency().cmp(&a.mean_latency())
+            .then(a_c.cmp(b_c)));
+        return keys;
+    }
+
+    // Keys with at least one miss sorted by misses, most missed first
+    pub fn most_missed_keys(&self) -> Vec<(char, &KeyStats)> {
+        let mut keys : Vec<(char, &KeyStats)> = self.key_stats.iter()
+            .filter(|(_, k)| k.misses > 0)
+            .map(|(c, k)| (*c, k))
+            .collect();
+        keys.sort_by(|(a_c, a), (b_c, b)| b.misses.cmp(&a.misses)
+            .then(b.error_rate().total_cmp(&a.error_rate()))
+            .then(a_c.cmp(b_c)));
+        return keys;
+    }
+
+    // Final stats are taken over the real elapsed time of the test
+    pub fn set_total_time(&mut self, total_time : Duration) {
+        self.total_time = total_time;
+        self.final_stats = Some(self.calc_stats());
     }
 
     fn calc_stats(&self) -> Stats {
-        let wpm = (self.chars_right) as f64 / 
-        self.total_time.as_secs_f64() * SECS_PER_MIN / CHARS_PER_WORD;
-        
-        let raw = (self.chars_right + self.chars_wrong) as f64 /
-        self.total_time.as_secs_f64() * SECS_PER_MIN / CHARS_PER_WORD;
-        
-        let acc = self.chars_right as f64 /
-        (self.chars_right + self.chars_wrong) as f64;
+        let secs = self.total_time.as_secs_f64();
+        let typed = self.chars_right + self.chars_wrong;
+
+        let mut wpm = 0.0;
+        let mut raw = 0.0;
+        if secs > 0.0 {
+            wpm = self.chars_right as f64 / secs * SECS_PER_MIN / CHARS_PER_WORD;
+            raw = typed as f64 / secs * SECS_PER_MIN / CHARS_PER_WORD;
+        }
+
+        let mut acc = 0.0;
+        if typed > 0 {
+            acc = self.chars_right as f64 / typed as f64;
+        }
 
         return Stats {
             wpm,
             raw,
             acc,
+            time : self.total_time,
+            right : self.chars_right,
+            wrong : self.chars_wrong,
         }
     }
 
+    fn last_stats(&self) -> Option<&Stats> {
+        return self.final_stats.as_ref().or(self.stats.last());
+    }
+
+    pub fn get_wpm(&self) -> f64 {
+        return self.last_stats().map_or(0.0, |s| s.wpm);
+    }
+
+    pub fn get_raw(&self) -> f64 {
+        return self.last_stats().map_or(0.0, |s| s.raw);
+    }
+
+    pub fn get_acc(&self) -> f64 {
+        return self.last_stats().map_or(0.0, |s| s.acc);
+    }
+
+    // (right, wrong, extra, missed)
+    pub fn get_counts(&self) -> (Count, Count, Count, Count) {
+        return (self.chars_right, self.chars_wrong, 
+            self.chars_extra, self.chars_missed);
+    }
+
+    pub fn get_total_time(&self) -> Duration {
+        return self.total_time;
+    }
+
     pub fn set_size(&mut self, size : (TermUnit, TermUnit)) {
         self.size = size;
     }
@@ -94,30 +199,208 @@ impl Punct {
     pub fn set_pos(&mut self, pos : (TermUnit, TermUnit)) {
         self.pos = pos;
     }
+
+    pub fn set_heatmap(&mut self, heatmap : Heatmap) {
+        self.heatmap = Some(heatmap);
+    }
+
+    pub fn set_theme(&mut self, theme : Theme) {
+        self.theme = theme;
+    }
+
+    pub fn set_title(&mut self, title : Option<String>) {
+        self.title = title;
+    }
+
+    pub fn get_title(&self) -> Option<String> {
+        return self.title.clone();
+    }
+
+    pub fn set_transforms(&mut self, transforms : Transforms) {
+        self.transforms = transforms;
+    }
+
+    pub fn get_transforms(&self) -> Transforms {
+        return self.transforms;
+    }
+
+    pub fn set_seed(&mut self, seed : Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn get_seed(&self) -> Option<u64> {
+        return self.seed;
+    }
+
+    pub fn get_samples(&self) -> Vec<Sample> {
+        return self.stats.iter()
+            .map(|s| Sample {
+                secs : s.time.as_secs_f64(),
+                right : s.right,
+                wrong : s.wrong,
+            })
+            .collect();
+    }
 }
 
-// REFACTOR
-impl Display for Punct {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+// Block of lines shown in the results screen
+struct Panel {
+    width : TermUnit,
+    lines : Vec<String>,
+}
 
-        let top = self.size.0 / 2 - 11 / 2;
-        let left = self.size.1 / 2 - 3 / 2;
-        let go_to_top_left = MoveTo(top, left);
-        let go_down = MoveDown(1);
+impl Panel {
+    // Lines without escape sequences
+    fn from_text(lines : Vec<String>) -> Panel {
+        let width = lines.iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0) as TermUnit;
+        return Panel {
+            width,
+            lines,
+        }
+    }
 
-        write!(f, "{go_to_top_left}")?;
+    fn height(&self) -> TermUnit {
+        return self.lines.len() as TermUnit;
+    }
+}
 
-        let raw = self.stats.last().unwrap().raw;
-        write!(f, "RAW  {raw:.2}")?;
-        write!(f, "{go_to_top_left}{go_down}")?;
+impl Punct {
+    fn summary_panel(&self) -> Panel {
+        let raw = self.get_raw();
+        let wpm = self.get_wpm();
+        let acc = self.get_acc() * 100.0;
+        let mut lines = vec![
+            format!("RAW  {raw:.2}"),
+            format!("WPM  {wpm:.2}"),
+            format!("ACC  {acc:.2}%"),
+        ];
+        if !self.transforms.is_empty() || self.seed.is_some() {
+            lines.push(String::new());
+        }
+        if !self.transforms.is_empty() {
+            lines.push(self.transforms.to_string());
+        }
+        if let Some(seed) = self.seed {
+            lines.push(format!("SEED {seed}"));
+        }
+        if let Some(title) = &self.title {
+            lines.push(String::new());
+            lines.push(format!("— {title}"));
+        }
+        return Panel::from_text(lines);
+    }
 
-        let wpm = self.stats.last().unwrap().wpm;
-        write!(f, "WPM  {wpm:.2}")?;
-        write!(f, "{go_to_top_left}{go_down}{go_down}")?;
+    fn keys_panel(&self) -> Option<Panel> {
+        let slowest = self.slowest_keys();
+        let missed = self.most_missed_keys();
+        if slowest.is_empty() && missed.is_empty() {
+            return None;
+        }
 
-        let acc = self.stats.last().unwrap().acc * 100.0;
-        write!(f, "ACC  {acc:.2}%")?;
-        write!(f, "{go_to_top_left}{go_down}{go_down}{go_down}")?;
+        let mut lines = vec![
+            format!("{:<6}{:>7}{:>8}    {:<6}{:>7}{:>7}",
+                "KEY", "MEAN", "MEDIAN", "KEY", "MISSED", "AS"),
+        ];
+        for i in 0..N_KEYS_SHOWN {
+            let mut line = String::new();
+            if let Some((c, k)) = slowest.get(i) {
+                let mean = k.mean_latency().unwrap_or_default().as_millis();
+                let median = k.median_latency().unwrap_or_default().as_millis();
+                line.push_str(&format!("{:<6}{:>5}ms{:>6}ms    ",
+                    key_name(*c), mean, median));
+            } else if i < missed.len() {
+                line.push_str(&" ".repeat(25));
+            }
+            if let Some((c, k)) = missed.get(i) {
+                let misses = format!("{}/{}", k.misses, k.presses);
+                let instead = k.most_typed_instead().map_or(String::new(), key_name);
+                line.push_str(&format!("{:<6}{:>7}{:>7}", key_name(*c), misses, instead));
+            }
+            if line.is_empty() { break; }
+            lines.push(line);
+        }
+        return Some(Panel::from_text(lines));
+    }
+
+    fn graph_panel(&self) -> Option<Panel> {
+        if self.stats.is_empty() {
+            return None;
+        }
+        let width = min(GRAPH_MAX_WIDTH, self.size.0);
+        let height = (self.size.1 / 3).clamp(GRAPH_MIN_HEIGHT, GRAPH_MAX_HEIGHT);
+        let graph = Graph::new((width, height), self.theme);
+
+        let right = self.theme.get_right();
+        let selected = self.theme.get_selected();
+        let wrong = self.theme.get_wrong();
+        let reset = Color::Reset;
+        let mut lines = vec![format!("{}• WPM  {}· RAW  {}x ERRORS{}",
+            SetForegroundColor(right), SetForegroundColor(selected),
+            SetForegroundColor(wrong), SetForegroundColor(reset))];
+        lines.extend(graph.lines(&self.get_samples()));
+        if lines.len() == 1 {
+            return None;
+        }
+
+        return Some(Panel {
+            width,
+            lines,
+        });
+    }
+
+    fn heatmap_panel(&self) -> Option<Panel> {
+        let heatmap = self.heatmap.as_ref()?;
+        if self.key_stats.is_empty() {
+            return None;
+        }
+        return Some(Panel {
+            width : heatmap.width(),
+            lines : heatmap.lines(&self.key_stats),
+        });
+    }
+}
+
+fn key_name(c : char) -> String {
+    return match c {
+        ' ' => String::from("space"),
+        '\n' => String::from("enter"),
+        c => c.to_string(),
+    }
+}
+
+impl Display for Punct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+
+        let mut panels = vec![self.summary_panel()];
+        panels.extend(self.graph_panel());
+        panels.extend(self.keys_panel());
+        panels.extend(self.heatmap_panel());
+
+        // Panels are shown one below the other while they fit
+        let mut height = 0;
+        let mut n_panels = 0;
+        for panel in &panels {
+            let panel_height = panel.height() + if n_panels > 0 { 1 } else { 0 };
+            if n_panels > 0 && 
+                (height + panel_height > self.size.1 || panel.width > self.size.0) { 
+                break; 
+            }
+            height += panel_height;
+            n_panels += 1;
+        }
+
+        let mut y = self.pos.1 + self.size.1.saturating_sub(height) / 2;
+        for panel in panels.iter().take(n_panels) {
+            let x = self.pos.0 + self.size.0.saturating_sub(panel.width) / 2;
+            for line in &panel.lines {
+                write!(f, "{}{line}", MoveTo(x, y))?;
+                y += 1;
+            }
+            y += 1;
+        }
 
         return Ok(());
     }
@@ -131,6 +414,8 @@ pub struct PunctWord {
     extra : Count,
     missed : Count,
     key_presses : Vec<KeyPress>,
+    // Every key press, including the ones deleted afterwards
+    key_log : Vec<KeyPress>,
     buffer_dur : Duration,
 }
 
@@ -143,13 +428,14 @@ impl PunctWord {
             extra : 0,
             missed : 0,
             key_presses : Vec::new(),
+            key_log : Vec::new(),
             buffer_dur : Duration::ZERO,
         }
     }
 
     pub fn add_key_press(&mut self, k : KeyPress) {
-        if k.pressed == ' ' {
-            // Spaces not counting for punct
+        if k.aim.is_whitespace() && k.pressed == k.aim {
+            // Spaces and line ends not counting for punct
         } else if k.pressed == k.aim {
             self.right += 1;
         } else if k.aim == '\0' {
@@ -157,6 +443,7 @@ impl PunctWord {
         } else {
             self.wrong += 1;
         }
+        self.key_log.push(k);
         let mut key_press = k;
         key_press.dur += self.buffer_dur;
         self.buffer_dur = Duration::ZERO;
@@ -170,9 +457,17 @@ impl PunctWord {
         self.key_presses.pop();
     }
 
+    pub fn set_missed(&mut self, missed : Count) {
+        self.missed = missed;
+    }
+
     pub fn get_punct(&self) -> (Count, Count, Count, Count, Count) {
         return (self.total, self.right, self.wrong, self.extra, self.missed)
     }
+
+    pub fn get_key_log(&self) -> &[KeyPress] {
+        return &self.key_log;
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -190,4 +485,119 @@ impl KeyPress {
             dur,
         }
     }
+
+    pub fn get_aim(&self) -> char { return self.aim; }
+
+    pub fn get_pressed(&self) -> char { return self.pressed; }
+
+    pub fn get_dur(&self) -> Duration { return self.dur; }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KeyStats {
+    presses : Count,
+    misses : Count,
+    latencies : Vec<Duration>,
+    typed_instead : HashMap<char, Count>,
+}
+
+impl KeyStats {
+    fn push(&mut self, key_press : &KeyPress) {
+        self.presses += 1;
+        if key_press.pressed != key_press.aim {
+            self.misses += 1;
+            *self.typed_instead.entry(key_press.pressed).or_default() += 1;
+        }
+        // The first key of the test has no previous key to measure from
+        if key_press.dur > Duration::ZERO {
+            self.latencies.push(key_press.dur);
+        }
+    }
+
+    pub fn merge(&mut self, other : &KeyStats) {
+        self.presses += other.presses;
+        self.misses += other.misses;
+        self.latencies.extend(&other.latencies);
+        for (c, n) in &other.typed_instead {
+            *self.typed_instead.entry(*c).or_default() += n;
+        }
+    }
+
+    pub fn get_presses(&self) -> Count { return self.presses; }
+
+    pub fn get_misses(&self) -> Count { return self.misses; }
+
+    pub fn error_rate(&self) -> f64 {
+        if self.presses == 0 { return 0.0; }
+        return self.misses as f64 / self.presses as f64;
+    }
+
+    pub fn mean_latency(&self) -> Option<Duration> {
+        if self.latencies.is_empty() { return None; }
+        let total : Duration = self.latencies.iter().sum();
+        return Some(total / self.latencies.len() as u32);
+    }
+
+    pub fn median_latency(&self) -> Option<Duration> {
+        if self.latencies.is_empty() { return None; }
+        let mut latencies = self.latencies.clone();
+        latencies.sort();
+        let mid = latencies.len() / 2;
+        if mid * 2 == latencies.len() {
+            return Some((latencies[mid - 1] + latencies[mid]) / 2);
+        } else {
+            return Some(latencies[mid]);
+        }
+    }
+
+    // The wrong char typed most times instead of this key
+    pub fn most_typed_instead(&self) -> Option<char> {
+        return self.typed_instead.iter()
+            .max_by(|(a_c, a), (b_c, b)| a.cmp(b).then(b_c.cmp(a_c)))
+            .map(|(c, _)| *c);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{KeyPress, Punct, PunctWord};
+
+    fn ms(millis : u64) -> Duration { return Duration::from_millis(millis); }
+
+    #[test]
+    fn key_stats_keep_every_press() {
+        let mut word = PunctWord::new(4);
+        word.add_key_press(KeyPress::new('a', 'a', ms(0)));
+        word.add_key_press(KeyPress::new('b', 'v', ms(300)));
+        word.sub_key_press();
+        word.add_key_press(KeyPress::new('b', 'b', ms(200)));
+        word.add_key_press(KeyPress::new('a', 's', ms(100)));
+        word.add_key_press(KeyPress::new('\0', 'x', ms(100)));
+        word.add_key_press(KeyPress::new(' ', ' ', ms(100)));
+
+        let mut punct = Punct::new();
+        punct.push_punct_word(&word);
+
+        let a = &punct.get_key_stats()[&'a'];
+        assert_eq!(a.get_presses(), 2);
+        assert_eq!(a.get_misses(), 1);
+        assert_eq!(a.mean_latency(), Some(ms(100)));
+        assert_eq!(a.most_typed_instead(), Some('s'));
+
+        let b = &punct.get_key_stats()[&'b'];
+        assert_eq!(b.get_presses(), 2);
+        assert_eq!(b.get_misses(), 1);
+        assert_eq!(b.mean_latency(), Some(ms(250)));
+        assert_eq!(b.median_latency(), Some(ms(250)));
+        assert_eq!(b.most_typed_instead(), Some('v'));
+
+        assert!(!punct.get_key_stats().contains_key(&'\0'));
+
+        let slowest : Vec<char> = punct.slowest_keys().iter().map(|(c, _)| *c).collect();
+        assert_eq!(slowest, vec!['b', ' ', 'a']);
+        let missed : Vec<char> = punct.most_missed_keys().iter().map(|(c, _)| *c).collect();
+        assert_eq!(missed, vec!['a', 'b']);
+    }
 }
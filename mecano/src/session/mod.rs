@@ -0,0 +1,343 @@
+//! Typing session model.
+//!
+//! A [`TypingSession`] holds the words of a test and scores what is typed on
+//! them. It does no I/O: it's fed chars, backspaces and timestamps, and asked
+//! for word states, the caret position and the metrics so far. The terminal
+//! text box draws one, but it can be driven by any other front end.
+//!
+//! ```
+//! use std::time::{Duration, Instant};
+//! use mecano::{config::TestKind, session::TypingSession};
+//!
+//! let mut session = TypingSession::new(TestKind::Words(2));
+//! session.push_word("hola");
+//! session.push_word("mundo");
+//!
+//! let start = Instant::now();
+//! session.resume(start);
+//! for (n, c) in "hola mundo ".chars().enumerate() {
+//!     session.type_char(c, start + Duration::from_millis(200 * (n as u64 + 1)));
+//! }
+//! assert!(session.is_finished());
+//!
+//! let punct = session.get_punct();
+//! assert_eq!(punct.get_counts(), (9, 0, 0, 0));
+//! assert_eq!(punct.get_total_time(), Duration::from_millis(2200));
+//! ```
+
+use std::{
+    cmp::min,
+    time::{Duration, Instant}
+};
+
+use crate::{
+    config::TestKind,
+    punctuation::Punct,
+    Count, Idx, TermUnit
+};
+
+pub use word::{StatefulChar, Word};
+
+pub mod word;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum State {
+    Right,
+    Wrong,
+    Unreached,
+    Selected,
+}
+
+#[derive(Debug)]
+pub struct TypingSession {
+    words : Vec<Word>,
+    selected_word : Idx,
+    kind : TestKind,
+    // Time counted before the last pause, and when the clock was resumed
+    counted : Duration,
+    resumed_at : Option<Instant>,
+    elapsed : Duration,
+    // Elapsed time when the last key was typed
+    last_key_at : Duration,
+    finished : bool,
+}
+
+impl TypingSession {
+    pub fn new(kind : TestKind) -> TypingSession {
+        return TypingSession {
+            words : Vec::new(),
+            selected_word : 0,
+            kind,
+            counted : Duration::ZERO,
+            resumed_at : None,
+            elapsed : Duration::ZERO,
+            last_key_at : Duration::ZERO,
+            finished : false,
+        }
+    }
+
+    // Words can be pushed while typing, the first one gets selected. Empty
+    // words have nothing to type and are left out
+    pub fn push_word(&mut self, word_str : &str) {
+        if word_str.trim().is_empty() {
+            return;
+        }
+        let mut word = Word::new(word_str);
+        if self.words.is_empty() {
+            word.select();
+        }
+        self.words.push(word);
+    }
+
+    // The last pushed word is ended by Enter instead of space
+    pub fn set_line_end(&mut self) {
+        if let Some(word) = self.words.last_mut() {
+            word.set_line_end();
+        }
+    }
+
+    pub fn get_words(&self) -> &[Word] {
+        return &self.words;
+    }
+
+    pub fn get_selected_word(&self) -> Idx {
+        return self.selected_word;
+    }
+
+    // Word and char the next key goes to, extra chars included
+    pub fn get_caret(&self) -> (Idx, Idx) {
+        return match self.words.get(self.selected_word) {
+            Some(word) => (self.selected_word, word.get_selected_char() + word.n_extra() as Idx),
+            None => (0, 0),
+        }
+    }
+
+    pub fn get_kind(&self) -> TestKind {
+        return self.kind;
+    }
+
+    // Char that ends the selected word
+    pub fn separator(&self) -> char {
+        return self.words.get(self.selected_word).map_or(' ', Word::separator);
+    }
+
+    // Keys are timed when they are read, not when the clock is drawn.
+    // Returns whether the key ended the selected word
+    pub fn type_char(&mut self, c : char, now : Instant) -> bool {
+        if self.words.is_empty() || self.is_finished() {
+            return false;
+        }
+        // Enter only ends lines
+        let separator = self.separator();
+        if c == '\n' && separator != '\n' {
+            return false;
+        }
+
+        self.update_time(now);
+        let key_duration = self.elapsed.saturating_sub(self.last_key_at);
+        self.words[self.selected_word].type_char(c, key_duration);
+        self.last_key_at = self.elapsed;
+
+        if c != separator && !self.ends_passage() {
+            return false;
+        }
+        self.next_word();
+        return true;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        if let Some(word) = self.words.get_mut(self.selected_word) {
+            word.pop();
+        }
+    }
+
+    // Reaching the last char of a passage ends it, no space needed
+    fn ends_passage(&self) -> bool {
+        return match self.kind {
+            TestKind::Passage(_) => self.selected_word + 1 >= self.words.len()
+                && self.words[self.selected_word].is_complete(),
+            _ => false,
+        }
+    }
+
+    fn next_word(&mut self) {
+        self.words[self.selected_word].unselect();
+
+        // Last word of a words test
+        if self.selected_word + 1 >= self.words.len() {
+            self.finished = true;
+            return;
+        }
+
+        self.selected_word += 1;
+        self.words[self.selected_word].select();
+    }
+
+    // Starts the clock, or resumes it after a pause
+    pub fn resume(&mut self, now : Instant) {
+        if self.resumed_at.is_none() {
+            self.resumed_at = Some(now);
+        }
+    }
+
+    pub fn pause(&mut self, now : Instant) {
+        self.update_time(now);
+        self.counted = self.elapsed;
+        self.resumed_at = None;
+    }
+
+    // Time is measured from timestamps, so it doesn't depend on how often
+    // it's updated
+    pub fn update_time(&mut self, now : Instant) -> bool {
+        if let Some(resumed_at) = self.resumed_at {
+            self.elapsed = self.counted + now.saturating_duration_since(resumed_at);
+        }
+        if let TestKind::Time(total_duration) = self.kind {
+            self.elapsed = min(self.elapsed, total_duration);
+        }
+        return !self.is_finished();
+    }
+
+    // Time left until the shown clock changes
+    pub fn until_clock_tick(&self) -> Duration {
+        let second = Duration::from_secs(1);
+        let nanos = self.get_clock().subsec_nanos();
+        return match self.kind {
+            TestKind::Time(_) if nanos > 0 => Duration::from_nanos(nanos as u64),
+            TestKind::Time(_) => second,
+            TestKind::Words(_) | TestKind::Passage(_) => second - Duration::from_nanos(nanos as u64),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return match self.kind {
+            TestKind::Time(total_duration) => self.elapsed >= total_duration,
+            TestKind::Words(_) | TestKind::Passage(_) => self.finished,
+        }
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        return self.elapsed;
+    }
+
+    // Remaining time on time tests, elapsed time on words tests
+    pub fn get_clock(&self) -> Duration {
+        return match self.kind {
+            TestKind::Time(total_duration) => total_duration - self.elapsed,
+            TestKind::Words(_) | TestKind::Passage(_) => self.elapsed,
+        }
+    }
+
+    // Scores so far, over the time elapsed at the last update
+    pub fn get_punct(&self) -> Punct {
+        let mut punct = Punct::new();
+        for word in &self.words {
+            punct.push_punct_word(word.get_punct());
+        }
+        punct.set_total_time(self.elapsed);
+        return punct;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use crate::config::TestKind;
+
+    use super::{State, TypingSession};
+
+    #[test]
+    fn tracks_word_states_and_caret() {
+        let mut session = TypingSession::new(TestKind::Words(2));
+        session.push_word("to");
+        session.push_word("be");
+        assert_eq!(session.get_caret(), (0, 0));
+        assert_eq!(session.get_words()[0].chars().next().unwrap().state, State::Selected);
+
+        let now = Instant::now();
+        assert!(!session.type_char('t', now));
+        assert!(!session.type_char('a', now));
+        assert!(!session.type_char('x', now));
+        assert_eq!(session.get_caret(), (0, 3));
+        let states : Vec<State> = session.get_words()[0].chars().map(|c| c.state).collect();
+        assert_eq!(states, vec![State::Right, State::Wrong]);
+        assert_eq!(session.get_words()[0].extra(), "x");
+
+        session.backspace();
+        session.backspace();
+        assert_eq!(session.get_caret(), (0, 1));
+        assert!(!session.type_char('o', now));
+        assert!(session.type_char(' ', now));
+        assert_eq!(session.get_caret(), (1, 0));
+        assert!(!session.get_words()[0].is_selected());
+        assert!(session.get_words()[1].is_selected());
+
+        // Enter only ends lines
+        assert!(!session.type_char('\n', now));
+        assert_eq!(session.get_caret(), (1, 0));
+    }
+
+    #[test]
+    fn live_metrics_follow_the_clock() {
+        let mut session = TypingSession::new(TestKind::Time(Duration::from_secs(60)));
+        session.push_word("hola");
+        session.push_word("mundo");
+
+        let start = Instant::now();
+        session.resume(start);
+        for (n, c) in "hola ".chars().enumerate() {
+            session.type_char(c, start + Duration::from_millis(100 * n as u64));
+        }
+        assert_eq!(session.get_punct().get_counts(), (4, 0, 0, 0));
+
+        session.update_time(start + Duration::from_secs(6));
+        let punct = session.get_punct();
+        assert_eq!(punct.get_total_time(), Duration::from_secs(6));
+        assert_eq!(punct.get_wpm(), 4.0 / 6.0 * 60.0 / 5.0);
+        assert_eq!(session.get_clock(), Duration::from_secs(54));
+        assert!(!session.is_finished());
+    }
+
+    #[test]
+    fn skips_empty_words() {
+        let mut session = TypingSession::new(TestKind::Words(2));
+        session.push_word("");
+        session.push_word("  \t");
+        session.push_word("si");
+        session.push_word(" ");
+        assert_eq!(session.get_words().len(), 1);
+        assert!(session.get_words()[0].is_selected());
+
+        assert!(!session.type_char('s', Instant::now()));
+        assert_eq!(session.get_caret(), (0, 1));
+    }
+
+    #[test]
+    fn score_is_frozen_when_finished() {
+        let mut session = TypingSession::new(TestKind::Words(1));
+        session.push_word("si");
+
+        let start = Instant::now();
+        session.resume(start);
+        for (n, c) in "si ".chars().enumerate() {
+            session.type_char(c, start + Duration::from_millis(100 * (n as u64 + 1)));
+        }
+        assert!(session.is_finished());
+        let (word, char) = session.get_caret();
+        let counts = session.get_punct().get_counts();
+
+        let later = start + Duration::from_secs(1);
+        assert!(!session.type_char('x', later));
+        assert!(!session.type_char(' ', later));
+        session.backspace();
+        assert_eq!(session.get_caret(), (word, char));
+        assert_eq!(session.get_words()[0].extra(), "");
+        let punct = session.get_punct();
+        assert_eq!(punct.get_counts(), counts);
+        assert_eq!(punct.get_total_time(), Duration::from_millis(300));
+    }
+}
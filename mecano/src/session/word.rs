@@ -7,19 +7,33 @@ use crate::punctuation::{KeyPress, PunctWord};
 
 use super::{Count, Idx, State, TermUnit};
 
+const TAB_WIDTH : TermUnit = 4;
+// Shown for spaces typed where a line end was expected
+const VISIBLE_SPACE : char = '·';
+
 #[derive(Debug)]
 pub struct Word {
     word : Vec<StatefulChar>,
     extra : String,
     selected_char : Idx,
     punct : PunctWord,
+    // Leading indentation, skipped when typing
+    indent : TermUnit,
+    // Ended by Enter instead of space
+    line_end : bool,
 }
 
 
 impl Word {
-    pub fn from_str(word_str : &str) -> Word {
+    pub fn new(word_str : &str) -> Word {
+        let trimmed = word_str.trim_start();
+        let indent = word_str[..word_str.len() - trimmed.len()]
+            .chars()
+            .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+            .sum();
+
         let mut word : Vec<StatefulChar> = Vec::new();
-        for c in word_str.chars() {
+        for c in trimmed.chars() {
             word.push(StatefulChar {c, state : State::Unreached});
         }
 
@@ -29,6 +43,8 @@ impl Word {
             extra : String::default(),
             selected_char : 0,
             punct,
+            indent,
+            line_end : false,
         }
     }
 
@@ -40,15 +56,19 @@ impl Word {
             extra : String::default(),
             selected_char : 0,
             punct,
+            indent : 0,
+            line_end : false,
         }
     }
 
     pub fn type_char(&mut self, c : char, dur : Duration) {
         // Next word
-        if c == ' ' {
+        if c == self.separator() {
             self.punct.add_key_press(
                 KeyPress::new(c, c, dur)
             );
+            let missed = self.word.len() - self.selected_char;
+            self.punct.set_missed(missed as Count);
 
         // No extra chars
         } else if self.selected_char < self.word.len() {
@@ -68,7 +88,7 @@ impl Word {
 
         // Extra chars
         } else {
-            self.extra.push(c);
+            self.extra.push(if c == ' ' { VISIBLE_SPACE } else { c });
             self.punct.add_key_press(
                 KeyPress::new('\0', c, dur)
             );
@@ -101,7 +121,9 @@ impl Word {
     }
 
     pub fn select(&mut self) {
-        self.word[0].state = State::Selected;
+        if let Some(c) = self.word.first_mut() {
+            c.state = State::Selected;
+        }
     }
 
     pub fn unselect(&mut self) {
@@ -116,11 +138,36 @@ impl Word {
         return self.word.iter().any(|s_c| s_c.state == State::Selected);
     }
 
+    // Chars reached, extra ones not counted
+    pub fn get_selected_char(&self) -> Idx {
+        return self.selected_char;
+    }
+
+    pub fn is_complete(&self) -> bool {
+        return self.selected_char >= self.word.len();
+    }
+
+    pub fn get_indent(&self) -> TermUnit {
+        return self.indent;
+    }
+
+    pub fn set_line_end(&mut self) {
+        self.line_end = true;
+    }
+
+    pub fn is_line_end(&self) -> bool {
+        return self.line_end;
+    }
+
+    pub fn separator(&self) -> char {
+        return if self.line_end { '\n' } else { ' ' };
+    }
+
     pub fn get_punct(&self) -> &PunctWord {
         return &self.punct;
     }
 
-    pub fn chars(&self) -> Iter<StatefulChar> {
+    pub fn chars(&self) -> Iter<'_, StatefulChar> {
         return self.word.iter();
     }
 
@@ -0,0 +1,33 @@
+use std::{fs, io, path::PathBuf};
+
+// Directory for the files of a test, removed with everything in it when
+// dropped, so a failing test leaves nothing behind
+pub struct TempDir {
+    path : PathBuf,
+}
+
+impl TempDir {
+    // Random names keep apart tests running at once, in this process or
+    // in another one
+    pub fn new(name : &str) -> TempDir {
+        loop {
+            let suffix : u64 = rand::random();
+            let path = std::env::temp_dir().join(format!("mecano_{name}_{suffix:016x}"));
+            match fs::create_dir(&path) {
+                Ok(()) => return TempDir { path },
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => panic!("couldn't create {}: {e}", path.display()),
+            }
+        }
+    }
+
+    pub fn join(&self, file : &str) -> PathBuf {
+        return self.path.join(file);
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
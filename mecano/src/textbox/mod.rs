@@ -1,8 +1,7 @@
 use std::{
     cmp::min,
-    fmt::{Display, Formatter},
-    io::{self, stdout, Write},
-    slice::Iter, time::Duration
+    io::{self, Write},
+    slice::Iter, time::{Duration, Instant}
 };
 
 use crossterm::{
@@ -11,182 +10,232 @@ use crossterm::{
     queue
 };
 
-use self::word::StatefulChar;
-
 use super::{Count, Idx, TermUnit};
 
 use crate::{
-    config::Theme, 
+    config::{TestKind, Theme}, 
     punctuation::Punct,
-    mode::WordSource,
+    mode::{code::LINE_END, WordSource},
+    session::{State, StatefulChar, TypingSession, Word},
 };
 
-use word::Word;
-
-mod word;
-
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum State {
-    Right,
-    Wrong,
-    Unreached,
-    Selected,
-}
-
+// Draws a typing session and keeps it fed with words from the source
 pub struct Text {
-    words : Vec<Word>,
+    session : TypingSession,
     words_source : Box<dyn WordSource>,
-    selected_word : Idx,
+    // Everything the source yielded, line ends too, to replay the test
+    yielded : Vec<String>,
     word_print_offset : Idx,
     line_chars : TermUnit,
     total_chars_to_show : Count,
     theme : Theme,
     size : (TermUnit, TermUnit),
     column : TermUnit,
-    total_duration : Duration,
-    last_key_duration : Duration,
 }
 
 impl Text {
 
     pub fn new(words_source : Box<dyn WordSource>, 
         config : Theme,
-        dur : Duration, 
+        kind : TestKind, 
         size : (TermUnit, TermUnit)) -> Text {
 
-        let words : Vec<Word> = Vec::new();
-
         let mut textbox =  Text {
-            words,
+            session : TypingSession::new(kind),
             words_source,
-            selected_word : 0,
+            yielded : Vec::new(),
             word_print_offset : 0,
             line_chars : 0,
             total_chars_to_show : 0,
             theme: config,
             size, 
             column : 0,
-            total_duration : dur,
-            last_key_duration : Duration::ZERO,
         };
 
         textbox.set_size(size);
-        if let Some(w) = textbox.words.get_mut(0) {
-            w.select();
+        if let Some(w) = textbox.words().first() {
+            textbox.line_chars = textbox.indent_width(w);
         }
         return textbox;
     }
 
-    fn print_word(&self, word : &Word, max_width : TermUnit) -> io::Result<TermUnit> {
-        return print_word(&self.theme, word, max_width);
+    fn words(&self) -> &[Word] {
+        return self.session.get_words();
+    }
+
+    fn print_word(&self, word : &Word, max_width : TermUnit, out : &mut impl Write) -> io::Result<TermUnit> {
+        return print_word(&self.theme, word, max_width, out);
     }
 
-    fn print_selected_word(&self) -> io::Result<TermUnit> {
-        self.go_to_selected_word()?;
+    // The word at the line position, which is the selected one until it's
+    // ended
+    fn print_word_at(&self, idx : Idx, out : &mut impl Write) -> io::Result<TermUnit> {
+        self.go_to_line_position(out)?;
         let result = self.print_word(
-            &self.words[self.selected_word], 
-            self.get_size_x() - self.line_chars
+            &self.words()[idx], 
+            self.get_size_x() - self.line_chars,
+            out
         );
         return result;
     }
 
 
-    fn go_to_selected_word(&self) -> io::Result<()> {
-        return queue!(stdout()
+    fn go_to_line_position(&self, out : &mut impl Write) -> io::Result<()> {
+        return queue!(out
             ,MoveToColumn(self.column + self.line_chars as TermUnit));
     }
 
-    pub fn type_char(&mut self, c : char) -> io::Result<()> {
-
-        let n_extra_before = self.words[self.selected_word].n_extra();
-        self.words[self.selected_word].type_char(c, self.last_key_duration);
-        self.last_key_duration = Duration::ZERO;
-        let n_extra_now = self.words[self.selected_word].n_extra();
+    pub fn type_char(&mut self, c : char, now : Instant, out : &mut impl Write) -> io::Result<()> {
+        let typed_word = self.session.get_selected_word();
+        let n_extra_before = self.words()[typed_word].n_extra();
+        let ends_word = self.session.type_char(c, now);
+        let n_extra_now = self.words()[typed_word].n_extra();
 
         // Print all if changed extra
         if n_extra_now != n_extra_before {
-            write!(stdout(), "{self}")?;
+            self.print(out)?;
         } else {
-            self.print_selected_word()?;
+            self.print_word_at(typed_word, out)?;
         }
 
-        if c.is_whitespace() {
-            self.next_word()?;
+        if ends_word {
+            self.next_word(typed_word, out)?;
         }
 
         return Ok(());
     }
 
-    fn next_word(&mut self) -> io::Result<()> {
-        // Unselect actual word
-        self.words[self.selected_word].unselect();
-        self.print_selected_word()?;
+    // Lines taken by the whole text with the chosen width
+    pub fn n_lines(&self, width : TermUnit) -> TermUnit {
+        let mut n_lines = 1;
+        let mut line_chars = 0;
+        let mut line_end = false;
+        for word in self.words() {
+            let n_chars = word.get_indent() + word.n_chars() + 1;
+            if line_end || (line_chars > 0 && line_chars + n_chars > width) {
+                n_lines += 1;
+                line_chars = 0;
+            }
+            line_chars += n_chars;
+            line_end = word.is_line_end();
+        }
+        return n_lines;
+    }
+
+    pub fn separator(&self) -> char {
+        return self.session.separator();
+    }
+
+    fn next_word(&mut self, typed_word : Idx, out : &mut impl Write) -> io::Result<()> {
+        self.words_source.feed(self.session.get_words()[typed_word].get_punct());
+
+        // Last word of a words test
+        let selected_word = self.session.get_selected_word();
+        if selected_word == typed_word {
+            return Ok(());
+        }
 
         // Update internal state
-        let n_word_chars = self.words[self.selected_word].n_chars_and_extra();
+        let line_end = self.words()[typed_word].is_line_end();
+        let n_word_chars = self.words()[typed_word].n_chars_and_extra();
         self.total_chars_to_show -= (n_word_chars + 1) as Count;
         self.line_chars += n_word_chars + 1;
 
-        // Select next word
-        self.selected_word += 1;
-        self.words[self.selected_word].select();
-
         // Checks if next line
-        let n_word_chars = self.words[self.selected_word].n_chars_and_extra(); 
-        if self.get_size_x() <= self.line_chars + n_word_chars {
-            self.word_print_offset = self.selected_word;
-            self.line_chars = 0;
+        let n_word_chars = self.words()[selected_word].n_chars_and_extra(); 
+        if line_end || self.get_size_x() <= self.line_chars + n_word_chars {
+            self.word_print_offset = selected_word;
+            self.line_chars = self.indent_width(&self.words()[selected_word]);
             self.complete_size();
-            write!(stdout(), "{self}")?;
+            self.print(out)?;
         } else {
-            self.print_selected_word()?;
+            self.print_word_at(selected_word, out)?;
         }
 
         return Ok(());
     }
 
-    pub fn backspace(&mut self) -> io::Result<()> {
-
-        let n_extra_before = self.words[self.selected_word].n_extra();
-        self.words[self.selected_word].pop();
-        let n_extra_now = self.words[self.selected_word].n_extra();
+    pub fn backspace(&mut self, out : &mut impl Write) -> io::Result<()> {
+        let selected_word = self.session.get_selected_word();
+        let n_extra_before = self.words()[selected_word].n_extra();
+        self.session.backspace();
+        let n_extra_now = self.words()[selected_word].n_extra();
 
         // Print change
         if n_extra_now != n_extra_before {
-            write!(stdout(), "{}", self)?;
+            self.print(out)?;
         } else {
-            self.print_selected_word()?;
+            self.print_word_at(selected_word, out)?;
         }
 
         return Ok(());
     }
 
-    pub fn update_time(&mut self, dur : Duration) -> bool {
-        self.total_duration -= min(dur, self.total_duration);
-        self.last_key_duration += dur;
-        return self.total_duration != Duration::ZERO;
+    pub fn resume(&mut self, now : Instant) {
+        self.session.resume(now);
     }
 
-    pub fn get_remaining_time(&mut self) -> &Duration {
-        return &self.total_duration; 
+    pub fn pause(&mut self, now : Instant) {
+        self.session.pause(now);
+    }
+
+    pub fn update_time(&mut self, now : Instant) -> bool {
+        return self.session.update_time(now);
+    }
+
+    pub fn until_clock_tick(&self) -> Duration {
+        return self.session.until_clock_tick();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.session.is_finished();
+    }
+
+    pub fn get_clock(&self) -> Duration {
+        return self.session.get_clock();
+    }
+
+    pub fn finish(&mut self, punct : &Punct) -> io::Result<()> {
+        return self.words_source.finish(punct);
+    }
+
+    pub fn get_yielded(&self) -> &[String] {
+        return &self.yielded;
     }
 
     pub fn get_punct(&self) -> Punct {
-        let mut punct = Punct::new();
-        for word in &self.words {
-            punct.push_punct_word(word.get_punct());
-        }
-        return punct;
+        return self.session.get_punct();
     }
 
     fn complete_size(&mut self) {
-        while self.total_chars_to_show < (self.get_size_x() * self.get_size_y()) as Count {
+        // A passage is loaded whole
+        let kind = self.session.get_kind();
+        let whole_passage = matches!(kind, TestKind::Passage(_));
+        while whole_passage || self.total_chars_to_show < (self.get_size_x() * self.get_size_y()) as Count {
+            if let TestKind::Words(n_words) | TestKind::Passage(n_words) = kind {
+                if self.words().len() as Count >= n_words { break; }
+            }
             let new_word = self.words_source.yield_word();
-            self.total_chars_to_show += (new_word.chars().count() + 1) as Count;
-            self.words.push(Word::from_str(new_word));
+            self.yielded.push(new_word.to_string());
+            // Line ends come as words of their own
+            if new_word == LINE_END {
+                self.session.set_line_end();
+                continue;
+            }
+            let n_words = self.session.get_words().len();
+            self.session.push_word(new_word);
+            if self.words().len() > n_words {
+                let n_chars = self.words().last().map_or(0, Word::n_chars);
+                self.total_chars_to_show += (n_chars + 1) as Count;
+            }
         }
     }
 
+    // Indentation shown before a word starting a line, as long as it fits
+    fn indent_width(&self, word : &Word) -> TermUnit {
+        return min(word.get_indent(), self.get_size_x().saturating_sub(word.n_chars() + 1));
+    }
+
     fn get_size_x(&self) -> TermUnit { return self.size.0; }
 
     fn get_size_y(&self) -> TermUnit { return self.size.1; }
@@ -197,102 +246,101 @@ impl Text {
     }
 
     pub fn set_column(&mut self, column : TermUnit) { self.column = column; }
-}
 
-
-
-impl Display for Text {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    // Draws the whole text box from the cursor line
+    pub fn print(&self, out : &mut impl Write) -> io::Result<()> {
         let go_to_column = MoveToColumn(self.column);
-        write!(f, "{go_to_column}")?;
+        write!(out, "{go_to_column}")?;
 
-        // Clean textbox stdout buffer
+        // Clean the text box
         let move_down = MoveDown(1);
         let blank_width = " ".repeat(self.get_size_x() as usize);
         for _ in 0..self.get_size_y() {
-            write!(f, "{blank_width}{move_down}{go_to_column}")?;
+            write!(out, "{blank_width}{move_down}{go_to_column}")?;
         }
 
         // Go back to beginning
         let move_up = MoveUp(1);
         for _ in 0..self.get_size_y() {
-            write!(f, "{move_up}")?;
+            write!(out, "{move_up}")?;
         }
-        write!(f, "{go_to_column}")?;
+        write!(out, "{go_to_column}")?;
 
         // Print words
         let mut remaining_width : TermUnit = self.get_size_x();
         let mut lenght : TermUnit = 0;
-        for idx in self.word_print_offset..self.words.len() {
-            let word = &self.words[idx];
+        let mut line_end = false;
+        for word in &self.words()[self.word_print_offset..] {
 
-            if remaining_width < word.n_chars() + 1 {
+            if line_end || remaining_width < word.n_chars() + 1 {
                 lenght += 1;
                 remaining_width = self.get_size_x();
-                write!(f, "{move_down}{go_to_column}")?;
+                write!(out, "{move_down}{go_to_column}")?;
             }
+            line_end = word.is_line_end();
 
             if lenght >= self.get_size_y() {
                 break;
             }
 
-            let result = self.print_word(word, remaining_width);
-            if let Ok(n_printed) = result {
-                remaining_width -= n_printed;
-            } else {
-                return std::fmt::Result::Err(std::fmt::Error);
+            if remaining_width == self.get_size_x() {
+                let indent = self.indent_width(word);
+                write!(out, "{}", " ".repeat(indent as usize))?;
+                remaining_width -= indent;
             }
+
+            remaining_width -= self.print_word(word, remaining_width, out)?;
         }
         return Ok(());
     }
 }
 
-fn print_word(config : &Theme, word : &Word, max_width : TermUnit) -> io::Result<TermUnit> {
+fn print_word(config : &Theme, word : &Word, max_width : TermUnit, out : &mut impl Write) -> io::Result<TermUnit> {
     // This case should be ckecked upfront
     assert!(max_width >= word.n_chars());
     if word.is_selected() {
-        queue!(stdout(), SetAttribute(Attribute::Underlined))?;
+        queue!(out, SetAttribute(Attribute::Underlined))?;
     }
 
     let n_chars_printed;
     // width enough for everything
     if max_width >= word.n_chars_and_extra() + 1 {
-        print_stateful_chars(config, word.chars())?;
+        print_stateful_chars(config, word.chars(), out)?;
         let extra = word.extra();
-        queue!(stdout(), SetForegroundColor(config.get_wrong()))?;
-        write!(stdout(), "{extra}")?;
-        queue!(stdout(), SetForegroundColor(Color::Reset))?;
+        queue!(out, SetForegroundColor(config.get_wrong()))?;
+        write!(out, "{extra}")?;
+        queue!(out, SetForegroundColor(Color::Reset))?;
         n_chars_printed = word.n_chars_and_extra() + 1; 
 
     // width enough for some extra chars
     } else if max_width > word.n_chars() + 1{
-        print_stateful_chars(config, word.chars())?;
+        print_stateful_chars(config, word.chars(), out)?;
         let remaining_width = max_width - word.n_chars();
 
         if remaining_width > 2 {
-            queue!(stdout(), SetForegroundColor(config.get_wrong()))?;
+            queue!(out, SetForegroundColor(config.get_wrong()))?;
             let extra_to_print = word
                 .extra()
                 .chars()
                 .take(remaining_width as usize - 2)
                 .collect::<String>();
-            write!(stdout(), "{extra_to_print}")?;
+            write!(out, "{extra_to_print}")?;
 
             let last_extra = word
                 .extra()
                 .chars()
                 .last()
                 .unwrap_or('\0');
-            write!(stdout(), "{last_extra}")?;
-            queue!(stdout(), SetForegroundColor(Color::Reset))?;
+            write!(out, "{last_extra}")?;
+            queue!(out, SetForegroundColor(Color::Reset))?;
         } else if remaining_width > 1 {
             let last_extra = word
                 .extra()
                 .chars()
                 .last()
                 .unwrap_or('\0');
-            write!(stdout(), "{last_extra}")?;
-            queue!(stdout(), SetForegroundColor(Color::Reset))?;
+            write!(out, "{last_extra}")?;
+            queue!(out, SetForegroundColor(Color::Reset))?;
         }
                         
         n_chars_printed = max_width;
@@ -303,29 +351,29 @@ fn print_word(config : &Theme, word : &Word, max_width : TermUnit) -> io::Result
             .chars()
             .take(word.n_chars() as usize - 1)
             .collect::<Word>();
-        print_stateful_chars(config, word_but_last.chars())?;
+        print_stateful_chars(config, word_but_last.chars(), out)?;
 
         if word.n_extra() > 0 {
-            queue!(stdout(), SetForegroundColor(config.get_wrong()))?;
+            queue!(out, SetForegroundColor(config.get_wrong()))?;
             let last_extra = word.extra().chars().last().unwrap();
-            write!(stdout(), "{last_extra}")?;
+            write!(out, "{last_extra}")?;
         } else {
-            queue!(stdout(), SetForegroundColor(config.get_right()))?;
+            queue!(out, SetForegroundColor(config.get_right()))?;
             let last_char = word.chars().last().unwrap().c;
-            write!(stdout(), "{last_char}")?;
+            write!(out, "{last_char}")?;
         }
-        queue!(stdout(), SetForegroundColor(Color::Reset))?;
+        queue!(out, SetForegroundColor(Color::Reset))?;
         n_chars_printed = max_width;
     }
 
     if word.is_selected() {
-        queue!(stdout(), SetAttribute(Attribute::Reset))?;
+        queue!(out, SetAttribute(Attribute::Reset))?;
     }
-    write!(stdout(), " ")?;
+    write!(out, " ")?;
     return Ok(n_chars_printed);
 }
 
-fn print_stateful_chars(config : &Theme, chars : Iter<StatefulChar>) -> io::Result<()> {
+fn print_stateful_chars(config : &Theme, chars : Iter<StatefulChar>, out : &mut impl Write) -> io::Result<()> {
     for character in chars {
         let color = match character.state {
             State::Right => config.get_right(),
@@ -333,7 +381,7 @@ fn print_stateful_chars(config : &Theme, chars : Iter<StatefulChar>) -> io::Resu
             State::Selected => config.get_selected(),
             State::Unreached => Color::Reset,
         };
-        queue!(stdout(), 
+        queue!(out, 
             SetForegroundColor(color),
             Print(character.c))?;
     }
@@ -343,18 +391,22 @@ fn print_stateful_chars(config : &Theme, chars : Iter<StatefulChar>) -> io::Resu
 #[cfg(test)]
 
 mod test {
-    use std::time::Duration;
+    use std::{io::sink, time::{Duration, Instant}};
+
+    use crossterm::style::{Attribute, Color};
+
+    use crate::{backend::VirtualScreen, config::{TestKind, Theme}, mode::{SourceCode, SourceFile}, path_to_file};
 
-    use crate::config::Theme;
+    use crate::session::Word;
 
-    use super::{print_word, word::Word};
+    use super::{print_word, Text};
 
 
 
 
     #[test]
     fn test_print_word() {
-        let mut word = Word::from_str("prueba");
+        let mut word = Word::new("prueba");
 
         let incorrect = "pruebaeeee";
 
@@ -363,6 +415,180 @@ mod test {
         }
 
         assert!(word.n_chars() == 6);
-        let _ = print_word(&Theme::default(), &word, 8);
+        let theme = Theme::default();
+        let mut screen = VirtualScreen::new((20, 1));
+        let _ = print_word(&theme, &word, 8, &mut screen);
+        // The last extra char takes the place of the last one that fits
+        assert_eq!(screen.line(0).trim_end(), "pruebae");
+        assert_eq!(screen.cell(0, 0).fg, theme.get_right());
+
+        let mut word = Word::new("mecano");
+        word.select();
+        word.type_char('m', Duration::ZERO);
+        word.type_char('a', Duration::ZERO);
+        let mut screen = VirtualScreen::new((20, 1));
+        let _ = print_word(&theme, &word, 20, &mut screen);
+        assert_eq!(screen.line(0).trim_end(), "mecano");
+        assert_eq!(screen.cell(0, 0).fg, theme.get_right());
+        assert_eq!(screen.cell(1, 0).fg, theme.get_wrong());
+        assert_eq!(screen.cell(5, 0).fg, Color::Reset);
+        assert!(screen.cell(0, 0).attributes.has(Attribute::Underlined));
+    }
+
+    #[test]
+    fn words_test_ends_on_last_word() {
+        let path = path_to_file("100_spanish").unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let mut text = Text::new(
+            Box::new(SourceFile::new(&path)),
+            Theme::default(),
+            TestKind::Words(3),
+            (80, 2));
+
+        let start = Instant::now();
+        text.resume(start);
+        for (n, word) in contents.split_whitespace().take(3).enumerate() {
+            assert!(!text.is_finished());
+            for c in word.chars() {
+                let _ = text.type_char(c, start + Duration::from_secs(n as u64), &mut sink());
+            }
+            let _ = text.type_char(' ', start + Duration::from_secs(n as u64 + 1), &mut sink());
+        }
+
+        assert!(text.is_finished());
+        assert_eq!(text.get_clock(), Duration::from_secs(3));
+
+        let (c_right, c_wrong, _, _, _, wpm, _) = text.get_punct().get_raw_info();
+        let typed : usize = contents.split_whitespace().take(3)
+            .map(|w| w.chars().count()).sum();
+        assert_eq!(c_right, typed as u64);
+        assert_eq!(c_wrong, 0);
+        assert_eq!(wpm, typed as f64 / 3.0 * 60.0 / 5.0);
+    }
+
+    #[test]
+    fn time_test_counts_down() {
+        let path = path_to_file("100_spanish").unwrap();
+        let mut text = Text::new(
+            Box::new(SourceFile::new(&path)),
+            Theme::default(),
+            TestKind::Time(Duration::from_secs(2)),
+            (80, 2));
+
+        let start = Instant::now();
+        text.resume(start);
+        assert!(text.update_time(start + Duration::from_millis(700)));
+        assert_eq!(text.until_clock_tick(), Duration::from_millis(300));
+        assert!(text.update_time(start + Duration::from_secs(1)));
+        assert_eq!(text.get_clock(), Duration::from_secs(1));
+        assert_eq!(text.until_clock_tick(), Duration::from_secs(1));
+
+        // Paused time isn't counted
+        text.pause(start + Duration::from_secs(1));
+        assert!(text.update_time(start + Duration::from_secs(30)));
+        text.resume(start + Duration::from_secs(30));
+        assert!(text.update_time(start + Duration::from_millis(30_500)));
+        assert_eq!(text.get_clock(), Duration::from_millis(500));
+        assert!(!text.update_time(start + Duration::from_secs(35)));
+        assert_eq!(text.get_clock(), Duration::ZERO);
+    }
+
+    #[test]
+    fn scores_ignore_update_rate() {
+        // Same keys at the same times, with the clock updated every 1ms,
+        // 7ms, 250ms or never
+        let typed = "de la que el ";
+        let play = |update_every : Option<Duration>| {
+            let mut text = Text::new(
+                Box::new(SourceFile::from_text(typed)),
+                Theme::default(),
+                TestKind::Words(4),
+                (80, 2));
+            let start = Instant::now();
+            text.resume(start);
+
+            let mut updated = Duration::ZERO;
+            for (n, c) in typed.chars().enumerate() {
+                let at = Duration::from_millis(150 * n as u64 + (n as u64 % 3) * 41);
+                if let Some(every) = update_every {
+                    while updated + every < at {
+                        updated += every;
+                        text.update_time(start + updated);
+                    }
+                }
+                let _ = text.type_char(c, start + at, &mut sink());
+            }
+            assert!(text.is_finished());
+            return text.get_punct();
+        };
+
+        let expected = play(None);
+        assert_eq!(expected.get_total_time(), Duration::from_millis(150 * 12));
+        assert_eq!(expected.get_key_stats()[&'q'].mean_latency(), Some(Duration::from_millis(150 - 2 * 41)));
+
+        for every in [1, 7, 250] {
+            let punct = play(Some(Duration::from_millis(every)));
+            assert_eq!(punct.get_raw_info(), expected.get_raw_info());
+            assert_eq!(punct.get_total_time(), expected.get_total_time());
+            for (c, stats) in expected.get_key_stats() {
+                assert_eq!(punct.get_key_stats()[c].mean_latency(), stats.mean_latency());
+            }
+        }
+    }
+
+    #[test]
+    fn code_expects_enter_at_line_ends() {
+        let code = SourceCode::from_text("if x {\n    y\n}\n").unwrap();
+        let mut text = Text::new(
+            Box::new(code),
+            Theme::default(),
+            TestKind::Words(5),
+            (80, 2));
+        let now = Instant::now();
+
+        for c in "if x".chars() {
+            let _ = text.type_char(c, now, &mut sink());
+        }
+        assert_eq!(text.separator(), ' ');
+        let _ = text.type_char('\n', now, &mut sink());
+        assert_eq!(text.separator(), ' ');
+        for c in " {".chars() {
+            let _ = text.type_char(c, now, &mut sink());
+        }
+
+        // A space at a line end is an extra char
+        assert_eq!(text.separator(), '\n');
+        let _ = text.type_char(' ', now, &mut sink());
+        let _ = text.backspace(&mut sink());
+        let _ = text.type_char('\n', now, &mut sink());
+
+        // Indentation is skipped
+        for c in "y\n}".chars() {
+            let _ = text.type_char(c, now, &mut sink());
+        }
+        assert!(!text.is_finished());
+        let _ = text.type_char(' ', now, &mut sink());
+        assert!(text.is_finished());
+
+        let (c_right, c_wrong, _, _, _, _, _) = text.get_punct().get_raw_info();
+        assert_eq!(c_right, 6);
+        assert_eq!(c_wrong, 0);
+    }
+
+    #[test]
+    fn passage_ends_on_last_char() {
+        let mut text = Text::new(
+            Box::new(SourceFile::from_text("to be")),
+            Theme::default(),
+            TestKind::Passage(2),
+            (80, 2));
+        let now = Instant::now();
+
+        for c in "to b".chars() {
+            let _ = text.type_char(c, now, &mut sink());
+        }
+        assert!(!text.is_finished());
+        let _ = text.type_char('e', now, &mut sink());
+        assert!(text.is_finished());
     }
 }